@@ -0,0 +1,78 @@
+//! # [`depmap-derive`](crate) - Derive macros for `depmap`
+//!
+//! This provides `#[derive(Dependencies)]`, implementing `depmap::DependencyProvider`. Use it
+//! through `depmap`'s `derive` feature.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, LitStr};
+
+/// Derives `depmap::DependencyProvider`.
+///
+/// The dependencies come from either a field marked `#[dependencies]`, which is cloned and
+/// iterated over, or a method named by `#[dependencies(method = "name")]` on the type, which
+/// must return something iterable over the type.
+#[proc_macro_derive(Dependencies, attributes(dependencies))]
+pub fn derive_dependencies(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+/// Expands the derive macro.
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    // Look for a method on the type first.
+    let mut source = None;
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("dependencies")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("method") {
+                let method = meta.value()?.parse::<LitStr>()?.parse::<syn::Ident>()?;
+                source = Some(quote!(self.#method()));
+                Ok(())
+            } else {
+                Err(meta.error("expected `method = \"...\"`"))
+            }
+        })?;
+    }
+
+    // Otherwise, look for a marked field.
+    if source.is_none() {
+        let fields = match &input.data {
+            Data::Struct(data) => &data.fields,
+            _ => return Err(Error::new_spanned(
+                name,
+                "expected a struct, or `#[dependencies(method = \"...\")]`",
+            )),
+        };
+        for (idx, field) in fields.iter().enumerate() {
+            if field.attrs.iter().any(|attr| attr.path().is_ident("dependencies")) {
+                if source.is_some() {
+                    return Err(Error::new_spanned(field, "only one field can be marked"));
+                }
+                let member = match (fields, &field.ident) {
+                    (Fields::Named(_), Some(ident)) => quote!(#ident),
+                    _ => {
+                        let idx = syn::Index::from(idx);
+                        quote!(#idx)
+                    },
+                };
+                source = Some(quote!(::std::clone::Clone::clone(&self.#member)));
+            }
+        }
+    }
+
+    let source = source.ok_or_else(|| Error::new_spanned(
+        name,
+        "expected a field marked `#[dependencies]`, or `#[dependencies(method = \"...\")]`",
+    ))?;
+    Ok(quote! {
+        impl #impl_generics ::depmap::DependencyProvider for #name #ty_generics #where_clause {
+            fn dependencies(&self) -> ::std::vec::Vec<Self> {
+                ::std::iter::IntoIterator::into_iter(#source).collect()
+            }
+        }
+    })
+}
@@ -0,0 +1,64 @@
+//! Producing dependencies for many targets at once.
+
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+use crate::{CyclePolicy, DepMap, Error};
+
+impl<T: Eq + Hash + Clone> DepMap<T> {
+    /// Runs through a whole dependency map using a producer function over batches of targets.
+    ///
+    /// Whenever the next target's dependencies are needed, the producer is given up to `batch`
+    /// targets: that one first, then the pending targets that will be expanded soonest. It returns
+    /// one list of dependencies per target, in order, and targets without a list are taken to have
+    /// no dependencies. This suits producers with batched lookups, like one query for many
+    /// packages.
+    pub fn process_batched<F, E>(initial: Vec<T>, batch: usize, mut f: F)
+        -> Result<Vec<T>, Error<T, E>>
+    where F: FnMut(&[&T]) -> Result<Vec<Vec<T>>, E> {
+        let batch = batch.max(1);
+        // Dependencies produced but not yet added.
+        let mut fetched = HashMap::<T, Vec<T>>::new();
+        // The current map.
+        let mut state = Self::new(initial);
+        loop {
+            match state.destroy() {
+                Ok(res) => break Ok(res),
+                Err(map) => state = map,
+            };
+
+            // Not empty; Produce a batch if needed, then process
+            let cur = state.current().unwrap();
+            if !fetched.contains_key(cur) {
+                let mut tgts = vec![cur];
+                let mut seen = HashSet::new();
+                seen.insert(cur);
+                // Pending targets, in the order they will be expanded: latest list first, and
+                // its targets in order if stable, otherwise from the end (see `take_first`).
+                'fill: for list in state.list[..state.used].iter().rev() {
+                    let rest: Box<dyn Iterator<Item = &T>> = if state.stable {
+                        Box::new(list[1..].iter())
+                    } else {
+                        Box::new(list[1..].iter().rev())
+                    };
+                    for tgt in rest {
+                        if tgts.len() >= batch {
+                            break 'fill
+                        }
+                        if !state.done.contains(tgt) && !fetched.contains_key(tgt)
+                            && seen.insert(tgt) {
+                            tgts.push(tgt);
+                        }
+                    }
+                }
+                let deps = (f)(&tgts).map_err(Error::UserDef)?;
+                fetched.extend(tgts.into_iter().cloned().zip(deps));
+            }
+            let deps = fetched.remove(state.current().unwrap()).unwrap_or_default();
+            if let Some((pos, dep)) = state.expand(|_| deps.into_iter().map(Ok),
+                &mut CyclePolicy::Fail)? {
+                break Err(Error::CyclicDep(state.take_cycle(pos, dep)))
+            }
+        }
+    }
+}
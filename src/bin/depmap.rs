@@ -0,0 +1,158 @@
+//! The `depmap` command-line tool.
+//!
+//! Reads a graph from standard input and writes its order, levels or cycles to standard output.
+
+use std::io::{self, Read};
+use std::process;
+
+use depmap::{DepMap, Export};
+
+/// The usage message.
+const USAGE: &str = "\
+usage: depmap [order|levels|cycles] [--format edges|json|dot]
+
+Reads a graph from standard input, each edge going from a target to a dependency.
+
+Commands:
+    order   Print every target, dependencies first, one per line (the default)
+    levels  Print every level of targets, one per line, separated by spaces
+    cycles  Print every cycle, one per line; exits with 1 if any are found
+
+Formats:
+    edges   One edge per line, as a target and a dependency separated by whitespace; a lone
+            target declares it, and '#' starts a comment (the default)
+    json    The JSON form of `DepMap::to_export`
+    dot     A Graphviz DOT digraph
+";
+
+/// What to print.
+enum Command {
+    Order,
+    Levels,
+    Cycles,
+}
+
+/// How the graph is read.
+enum Format {
+    Edges,
+    Json,
+    Dot,
+}
+
+fn main() {
+    let (cmd, format) = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(msg) => fail(&format!("{}\n\n{}", msg, USAGE)),
+    };
+
+    let mut src = String::new();
+    if let Err(err) = io::stdin().read_to_string(&mut src) {
+        fail(&format!("reading standard input: {}", err));
+    }
+    let map = match read(&src, format) {
+        Ok(map) => map,
+        Err(msg) => fail(&msg),
+    };
+
+    match cmd {
+        Command::Order => match map.toposort() {
+            Ok(order) => {
+                for tgt in order {
+                    println!("{}", tgt);
+                }
+            },
+            Err(err) => fail(&err.to_string()),
+        },
+        Command::Levels => match map.levels() {
+            Ok(levels) => {
+                for level in levels {
+                    println!("{}", level.join(" "));
+                }
+            },
+            Err(err) => fail(&err.to_string()),
+        },
+        Command::Cycles => {
+            let cycles = map.all_cycles();
+            for cycle in &cycles {
+                let chain = cycle.iter().map(|tgt| tgt.as_str()).collect::<Vec<_>>();
+                println!("{} -> {}", chain.join(" -> "), chain[0]);
+            }
+            if !cycles.is_empty() {
+                process::exit(1);
+            }
+        },
+    }
+}
+
+/// Parses the command and format from the arguments.
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<(Command, Format), String> {
+    let mut cmd = None;
+    let mut format = Format::Edges;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                print!("{}", USAGE);
+                process::exit(0);
+            },
+            "--format" | "-f" => {
+                format = match args.next().as_deref() {
+                    Some("edges") => Format::Edges,
+                    Some("json") => Format::Json,
+                    Some("dot") => Format::Dot,
+                    Some(other) => return Err(format!("unknown format '{}'", other)),
+                    None => return Err("missing format".to_string()),
+                };
+            },
+            _ if cmd.is_some() => return Err(format!("unexpected argument '{}'", arg)),
+            "order" => cmd = Some(Command::Order),
+            "levels" => cmd = Some(Command::Levels),
+            "cycles" => cmd = Some(Command::Cycles),
+            _ => return Err(format!("unknown command '{}'", arg)),
+        }
+    }
+    Ok((cmd.unwrap_or(Command::Order), format))
+}
+
+/// Reads a graph in the given format.
+fn read(src: &str, format: Format) -> Result<DepMap<String>, String> {
+    match format {
+        Format::Edges => {
+            let mut nodes = Vec::new();
+            let mut edges = Vec::new();
+            for (i, line) in src.lines().enumerate() {
+                let line = line.split('#').next().unwrap();
+                let words = line.split_whitespace().collect::<Vec<_>>();
+                match words[..] {
+                    [] => {},
+                    [tgt] => nodes.push(tgt.to_string()),
+                    [tgt, dep] => {
+                        nodes.push(tgt.to_string());
+                        edges.push((tgt.to_string(), dep.to_string()));
+                    },
+                    _ => return Err(format!("line {}: expected a target and a dependency", i + 1)),
+                }
+            }
+            Ok(DepMap::from_graph(nodes, edges))
+        },
+        Format::Json => {
+            let export = serde_json::from_str::<Export<String>>(src)
+                .map_err(|err| format!("invalid JSON: {}", err))?;
+            let mut edges = Vec::new();
+            for edge in &export.edges {
+                let node = |id: usize| export.nodes.get(id)
+                    .map(|node| node.target.clone())
+                    .ok_or_else(|| format!("no node with id {}", id));
+                edges.push((node(edge.from)?, node(edge.to)?));
+            }
+            let nodes = export.nodes.into_iter().map(|node| node.target);
+            Ok(DepMap::from_graph(nodes, edges))
+        },
+        Format::Dot => DepMap::from_dot(src).map_err(|err| err.to_string()),
+    }
+}
+
+/// Prints an error and exits.
+fn fail(msg: &str) -> ! {
+    eprintln!("depmap: {}", msg);
+    process::exit(1)
+}
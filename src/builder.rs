@@ -0,0 +1,216 @@
+//! Configuring dependency maps.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::mem;
+
+use crate::{CyclePolicy, DepMap, DepMapConfig, Error, Event};
+
+/// A builder collecting the options of a dependency map.
+///
+/// Every option is applied by [`build`](Self::build). Options of how the map is processed are
+/// collected by a [`ProcessBuilder`]; see [`processing`](Self::processing).
+#[derive(Clone, Debug)]
+pub struct DepMapBuilder<T> {
+    /// The configuration.
+    config: DepMapConfig,
+    /// Whether lists are handled in order.
+    stable: bool,
+    /// Whether dependencies are recorded.
+    edges: bool,
+    /// The targets already done.
+    done: Vec<T>,
+}
+
+impl<T: Eq + Hash + Clone> DepMapBuilder<T> {
+    /// Creates a new [`DepMapBuilder`] with the default options.
+    pub fn new() -> Self {
+        Self {
+            config: DepMapConfig::default(),
+            stable: false,
+            edges: false,
+            done: Vec::new(),
+        }
+    }
+
+    /// Sets the configuration, replacing any limits set so far.
+    pub fn config(mut self, config: DepMapConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Sets the maximum number of active targets.
+    pub fn max_depth(mut self, max: usize) -> Self {
+        self.config.max_depth = Some(max);
+        self
+    }
+
+    /// Sets the maximum number of distinct targets completed or pending.
+    pub fn max_nodes(mut self, max: usize) -> Self {
+        self.config.max_nodes = Some(max);
+        self
+    }
+
+    /// Makes the map handle the dependencies of each target in the order they were produced.
+    ///
+    /// See [`DepMap::with_stable_order`].
+    pub fn stable_order(mut self) -> Self {
+        self.stable = true;
+        self
+    }
+
+    /// Makes the map record the dependencies of every target it expands.
+    ///
+    /// See [`DepMap::with_edges`].
+    pub fn record_edges(mut self) -> Self {
+        self.edges = true;
+        self
+    }
+
+    /// Adds targets already done.
+    ///
+    /// See [`DepMap::with_done`].
+    pub fn done<I: IntoIterator<Item = T>>(mut self, done: I) -> Self {
+        self.done.extend(done);
+        self
+    }
+
+    /// Creates a new [`DepMap`] from an initial list, with every option.
+    pub fn build(&self, initial: Vec<T>) -> DepMap<T> {
+        let mut map = DepMap::with_done(initial, self.done.iter().cloned())
+            .with_config(self.config);
+        if self.stable {
+            map = map.with_stable_order();
+        }
+        if self.edges {
+            map = map.with_edges();
+        }
+        map
+    }
+
+    /// Goes on to collect options of how the map is processed.
+    pub fn processing<'a>(self) -> ProcessBuilder<'a, T> {
+        ProcessBuilder {
+            map: self,
+            policy: CyclePolicy::Fail,
+            observer: None,
+            skip: Vec::new(),
+            phony: Vec::new(),
+            dedup: None,
+        }
+    }
+}
+
+impl<T: Eq + Hash + Clone> Default for DepMapBuilder<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A builder collecting the options of a dependency map and of how it is processed.
+///
+/// Created by [`DepMapBuilder::processing`]. Every option is applied by
+/// [`process`](Self::process).
+#[allow(clippy::type_complexity)]
+pub struct ProcessBuilder<'a, T> {
+    /// The options of the map itself.
+    map: DepMapBuilder<T>,
+    /// How cyclic dependencies are handled.
+    policy: CyclePolicy<'a, T>,
+    /// The observer, if any.
+    observer: Option<Box<dyn FnMut(Event<'_, T>) + 'a>>,
+    /// Predicates matching targets to skip.
+    skip: Vec<Box<dyn Fn(&T) -> bool + 'a>>,
+    /// Predicates matching phony targets.
+    phony: Vec<Box<dyn Fn(&T) -> bool + 'a>>,
+    /// Replaces every target with the first one found equal to it, if targets are compared by
+    /// key.
+    dedup: Option<Box<dyn Fn(T) -> T + 'a>>,
+}
+
+impl<'a, T: Eq + Hash + Clone> ProcessBuilder<'a, T> {
+    /// Sets how cyclic dependencies are handled.
+    pub fn cycle_policy(mut self, policy: CyclePolicy<'a, T>) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Sets an observer, reported what happens while processing.
+    ///
+    /// See [`DepMap::process_observed`].
+    pub fn observer<O: FnMut(Event<'_, T>) + 'a>(mut self, observer: O) -> Self {
+        self.observer = Some(Box::new(observer));
+        self
+    }
+
+    /// Skips targets matching a predicate, treating them as already done.
+    ///
+    /// Skipped targets are never expanded and are left out of the result list, without every
+    /// producer having to filter them. Targets matching any predicate given are skipped.
+    pub fn skip_if<P: Fn(&T) -> bool + 'a>(mut self, pred: P) -> Self {
+        self.skip.push(Box::new(pred));
+        self
+    }
+
+    /// Marks targets matching a predicate as phony, like grouping targets such as `all`.
+    ///
+    /// Phony targets are expanded and ordered like any other, but are left out of the result
+    /// list. Targets matching any predicate given are phony.
+    pub fn phony_if<P: Fn(&T) -> bool + 'a>(mut self, pred: P) -> Self {
+        self.phony.push(Box::new(pred));
+        self
+    }
+
+    /// Treats targets with equal keys as duplicates, rather than only equal targets.
+    ///
+    /// The first target found with every key is kept, and is what the producer and predicates
+    /// are given; see [`process_keyed`](DepMap::process_keyed).
+    pub fn dedup_by<K, G>(mut self, key: G) -> Self
+    where K: Eq + Hash + 'a, G: Fn(&T) -> K + 'a, T: 'a {
+        // The first target found with every key.
+        let first = RefCell::new(HashMap::new());
+        self.dedup = Some(Box::new(move |tgt| {
+            first.borrow_mut().entry((key)(&tgt)).or_insert(tgt).clone()
+        }));
+        self
+    }
+
+    /// Runs through a whole dependency map with every option.
+    pub fn process<F, I, E>(mut self, initial: Vec<T>, mut f: F) -> Result<Vec<T>, Error<T, E>>
+    where F: FnMut(&T) -> I, I: IntoIterator<Item = Result<T, E>> {
+        let dedup = self.dedup.take();
+        let dedup = |tgt: T| match &dedup {
+            Some(dedup) => (dedup)(tgt),
+            None => tgt,
+        };
+        let skip = mem::take(&mut self.skip);
+        let skip = |tgt: &T| skip.iter().any(|pred| (pred)(tgt));
+        let initial = initial.into_iter()
+            .map(dedup)
+            .filter(|tgt| !skip(tgt))
+            .collect();
+        self.map.done = mem::take(&mut self.map.done).into_iter().map(dedup).collect();
+        // Drop skipped dependencies as they are produced.
+        let f = |tgt: &T| (f)(tgt).into_iter()
+            .map(|dep| dep.map(dedup))
+            .filter(|dep| !matches!(dep, Ok(dep) if skip(dep)));
+        let map = self.map.build(initial);
+        let phony = mem::take(&mut self.phony);
+        let mut res = match self.observer {
+            Some(observer) => map.finish_observed(self.policy, observer, f),
+            None => map.finish(&mut self.policy, f),
+        }?;
+        if !phony.is_empty() {
+            res.retain(|tgt| !phony.iter().any(|pred| (pred)(tgt)));
+        }
+        Ok(res)
+    }
+}
+
+impl<T: Eq + Hash + Clone> DepMap<T> {
+    /// Creates a new [`DepMapBuilder`].
+    pub fn builder() -> DepMapBuilder<T> {
+        DepMapBuilder::new()
+    }
+}
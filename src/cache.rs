@@ -0,0 +1,58 @@
+//! Memoizing producer functions.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// A producer function adapter that remembers the dependencies of every target.
+///
+/// The wrapped function is only called once per target (unless it fails), no matter how many
+/// times or in how many dependency maps the target is expanded.
+pub struct CachedResolver<T, F> {
+    /// The wrapped producer function.
+    f: F,
+    /// The dependencies of every target seen so far.
+    cache: HashMap<T, Vec<T>>,
+}
+
+impl<T: Eq + Hash + Clone, F> CachedResolver<T, F> {
+    /// Creates a new [`CachedResolver`] wrapping a producer function.
+    pub fn new(f: F) -> Self {
+        Self {
+            f,
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Returns the dependencies of a target, calling the wrapped function if necessary.
+    ///
+    /// This is meant to be called from the producer function given to a dependency map.
+    pub fn resolve<I, E>(&mut self, tgt: &T) -> impl Iterator<Item = Result<T, E>>
+    where F: FnMut(&T) -> I, I: IntoIterator<Item = Result<T, E>> {
+        let (deps, err) = match self.cache.get(tgt) {
+            Some(deps) => (deps.clone(), None),
+            None => match (self.f)(tgt).into_iter().collect::<Result<Vec<_>, _>>() {
+                Ok(deps) => {
+                    self.cache.insert(tgt.clone(), deps.clone());
+                    (deps, None)
+                },
+                Err(err) => (Vec::new(), Some(err)),
+            },
+        };
+        err.map(Err).into_iter().chain(deps.into_iter().map(Ok))
+    }
+
+    /// Forgets the dependencies of a target, so they are produced again next time.
+    pub fn invalidate(&mut self, tgt: &T) {
+        self.cache.remove(tgt);
+    }
+
+    /// Forgets the dependencies of every target.
+    pub fn clear(&mut self) {
+        self.cache.clear();
+    }
+
+    /// Returns the wrapped producer function.
+    pub fn into_inner(self) -> F {
+        self.f
+    }
+}
@@ -0,0 +1,103 @@
+//! Checkpointing and resuming dependency maps.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::Hash;
+
+use crate::{DepMap, DepMapConfig};
+
+/// A snapshot of a dependency map, from which it can be resumed later.
+///
+/// With the `serde` feature, this can be stored and resumed in another process. Its layout is
+/// independent of the map's internals, and is versioned by [`Checkpoint::VERSION`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Checkpoint<T> {
+    /// The version of the format.
+    version: u32,
+    /// The completed targets, in order.
+    result: Vec<T>,
+    /// The targets done but left out of the result list.
+    skipped: Vec<T>,
+    /// The pending lists, outermost first. The first of each list is active.
+    pending: Vec<Vec<T>>,
+    /// The recorded dependencies, if they are being recorded.
+    edges: Option<Vec<(T, Vec<T>)>>,
+    /// The first target to depend on every recorded dependency.
+    parents: Vec<(T, T)>,
+    /// Whether lists are handled in order.
+    stable: bool,
+    /// The configuration.
+    config: DepMapConfig,
+}
+
+/// An error resuming from a checkpoint of an unknown format version.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UnknownVersion(pub u32);
+
+impl fmt::Display for UnknownVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown checkpoint version {}", self.0)
+    }
+}
+
+impl std::error::Error for UnknownVersion {}
+
+impl<T> Checkpoint<T> {
+    /// The current version of the format.
+    pub const VERSION: u32 = 1;
+
+    /// Returns the version of the format this checkpoint was made with.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+}
+
+impl<T: Eq + Hash + Clone> DepMap<T> {
+    /// Takes a snapshot of the map, from which it can be resumed.
+    pub fn checkpoint(&self) -> Checkpoint<T> {
+        let result = self.result.iter().collect::<HashSet<_>>();
+        Checkpoint {
+            version: Checkpoint::<T>::VERSION,
+            skipped: self.done.iter()
+                .filter(|tgt| !result.contains(tgt))
+                .cloned()
+                .collect(),
+            result: self.result.clone(),
+            pending: self.list[..self.used].to_vec(),
+            edges: self.edges.as_ref().map(|edges| edges.iter()
+                .map(|(tgt, deps)| (tgt.clone(), deps.clone()))
+                .collect()),
+            parents: self.parents.iter()
+                .map(|(tgt, parent)| (tgt.clone(), parent.clone()))
+                .collect(),
+            stable: self.stable,
+            config: self.config,
+        }
+    }
+
+    /// Resumes a map from a snapshot.
+    ///
+    /// Fails if the snapshot was made with an unknown version of the format.
+    pub fn resume(checkpoint: Checkpoint<T>) -> Result<Self, UnknownVersion> {
+        if checkpoint.version != Checkpoint::<T>::VERSION {
+            return Err(UnknownVersion(checkpoint.version))
+        }
+
+        let mut map = Self::new(Vec::new());
+        map.list.clear();
+        map.done = checkpoint.result.iter()
+            .chain(&checkpoint.skipped)
+            .cloned()
+            .collect();
+        map.result = checkpoint.result;
+        for list in checkpoint.pending.into_iter().filter(|list| !list.is_empty()) {
+            map.push_used(list);
+        }
+        map.edges = checkpoint.edges.map(|edges| edges.into_iter().collect::<HashMap<_, _>>());
+        map.parents = checkpoint.parents.into_iter().collect();
+        map.stable = checkpoint.stable;
+        map.config = checkpoint.config;
+        Ok(map)
+    }
+}
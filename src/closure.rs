@@ -0,0 +1,107 @@
+//! Reachability between targets.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::{components, DepMap};
+
+/// Which targets every target depends on, directly or not.
+///
+/// Returned by [`DepMap::closure`]. Every target has a row of bits, one per target, so checks
+/// need no walking of the graph.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Closure<T: Eq + Hash + Clone> {
+    /// Every target, in a stable order.
+    nodes: Vec<T>,
+    /// The position of every target.
+    index: HashMap<T, usize>,
+    /// The number of words in each row.
+    words: usize,
+    /// The rows of every target, one after another.
+    bits: Vec<u64>,
+}
+
+impl<T: Eq + Hash + Clone> Closure<T> {
+    /// Whether a target depends on another, directly or not.
+    ///
+    /// Targets only depend on themselves through cycles. Unknown targets depend on nothing.
+    pub fn reaches(&self, from: &T, to: &T) -> bool {
+        match (self.index.get(from), self.index.get(to)) {
+            (Some(&from), Some(&to)) => self.bit(from, to),
+            _ => false,
+        }
+    }
+
+    /// Everything a target depends on, directly or not, in a stable order.
+    pub fn reachable(&self, tgt: &T) -> Vec<&T> {
+        match self.index.get(tgt) {
+            Some(&tgt) => self.nodes.iter()
+                .enumerate()
+                .filter(|&(i, _)| self.bit(tgt, i))
+                .map(|(_, tgt)| tgt)
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// The number of targets.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Whether there are no targets.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Whether the target at one position depends on the one at another.
+    fn bit(&self, from: usize, to: usize) -> bool {
+        self.bits[from * self.words + to / 64] & (1 << (to % 64)) != 0
+    }
+}
+
+impl<T: Eq + Hash + Clone> DepMap<T> {
+    /// Works out which targets every known target depends on, using the recorded edges.
+    ///
+    /// Cycles are allowed: every target in one depends on every other, and on itself.
+    pub fn closure(&self) -> Closure<T> {
+        let nodes = self.nodes();
+        let index = nodes.iter()
+            .enumerate()
+            .map(|(i, tgt)| (tgt.clone(), i))
+            .collect::<HashMap<_, _>>();
+        let words = nodes.len().div_ceil(64);
+        let mut bits = vec![0u64; nodes.len() * words];
+        let empty = HashMap::new();
+        let edges = self.edges.as_ref().unwrap_or(&empty);
+
+        // Groups come in dependency order, so the rows of dependencies outside a group are done.
+        for group in components(nodes.clone(), edges) {
+            let members = group.iter().map(|tgt| index[tgt]).collect::<Vec<_>>();
+            let mut row = vec![0u64; words];
+            for &tgt in &members {
+                for dep in self.recorded(&nodes[tgt]) {
+                    let dep = index[dep];
+                    row[dep / 64] |= 1 << (dep % 64);
+                    if !members.contains(&dep) {
+                        let start = dep * words;
+                        for (word, &dep_word) in row.iter_mut().zip(&bits[start..start + words]) {
+                            *word |= dep_word;
+                        }
+                    }
+                }
+            }
+            if members.len() > 1 {
+                // Every target in a cycle reaches every other.
+                for &tgt in &members {
+                    row[tgt / 64] |= 1 << (tgt % 64);
+                }
+            }
+            for &tgt in &members {
+                bits[tgt * words..(tgt + 1) * words].copy_from_slice(&row);
+            }
+        }
+
+        Closure { nodes, index, words, bits }
+    }
+}
@@ -0,0 +1,131 @@
+//! Sharing dependency maps between threads.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+use std::sync::Mutex;
+
+use crate::{DepMap, Error};
+
+/// A thread-safe scheduler that targets can be added to while it is worked on.
+///
+/// Worker threads pull targets whose dependencies are complete with
+/// [`next_ready`](Self::next_ready) and report them with [`complete`](Self::complete), sharing
+/// the map by reference (e.g through an `Arc` or scoped threads) rather than funneling through a
+/// single owner. Unlike a [`Scheduler`](crate::Scheduler), new roots can be added at any time.
+pub struct ConcurrentDepMap<T: Eq + Hash + Clone> {
+    /// The shared state.
+    state: Mutex<State<T>>,
+}
+
+/// The state of a [`ConcurrentDepMap`].
+struct State<T> {
+    /// Every target added, complete or not.
+    known: HashSet<T>,
+    /// The completed targets.
+    complete: HashSet<T>,
+    /// The number of incomplete dependencies of every target not yet handed out.
+    waiting: HashMap<T, usize>,
+    /// The dependents of every incomplete target.
+    dependents: HashMap<T, Vec<T>>,
+    /// The targets ready to be handed out.
+    ready: VecDeque<T>,
+    /// The targets handed out but not yet completed.
+    running: HashSet<T>,
+}
+
+impl<T: Eq + Hash + Clone> ConcurrentDepMap<T> {
+    /// Creates a new, empty [`ConcurrentDepMap`].
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(State {
+                known: HashSet::new(),
+                complete: HashSet::new(),
+                waiting: HashMap::new(),
+                dependents: HashMap::new(),
+                ready: VecDeque::new(),
+                running: HashSet::new(),
+            }),
+        }
+    }
+
+    /// Adds a root target, along with every dependency not already added, using a producer
+    /// function.
+    ///
+    /// Dependencies are produced without holding the lock, so workers are not held up. Targets
+    /// already added are not produced again, and are completed as usual.
+    pub fn add_root<F, I, E>(&self, root: T, f: F) -> Result<(), Error<T, E>>
+    where F: FnMut(&T) -> I, I: IntoIterator<Item = Result<T, E>> {
+        let known = self.state.lock().unwrap().known.clone();
+        let mut map = DepMap::with_done(vec![root], known).with_edges();
+        map.add_all(f)?;
+        let deps = map.edges.unwrap_or_default();
+
+        let mut state = self.state.lock().unwrap();
+        for tgt in map.result {
+            // It may have been added by another thread meanwhile.
+            if !state.known.insert(tgt.clone()) {
+                continue;
+            }
+            let uniq = deps.get(&tgt).into_iter()
+                .flatten()
+                .filter(|dep| !state.complete.contains(*dep))
+                .collect::<HashSet<_>>();
+            for dep in &uniq {
+                state.dependents.entry((*dep).clone()).or_default().push(tgt.clone());
+            }
+            if uniq.is_empty() {
+                state.ready.push_back(tgt);
+            } else {
+                state.waiting.insert(tgt, uniq.len());
+            }
+        }
+        Ok(())
+    }
+
+    /// Hands out a target whose dependencies are all complete, if any.
+    ///
+    /// The target is considered running until it is completed.
+    pub fn next_ready(&self) -> Option<T> {
+        let mut state = self.state.lock().unwrap();
+        let tgt = state.ready.pop_front()?;
+        state.running.insert(tgt.clone());
+        Some(tgt)
+    }
+
+    /// Marks a running target as complete, readying its dependents.
+    ///
+    /// Returns whether the target was running.
+    pub fn complete(&self, tgt: &T) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if !state.running.remove(tgt) {
+            return false
+        }
+        state.complete.insert(tgt.clone());
+        for dep in state.dependents.remove(tgt).into_iter().flatten() {
+            let count = state.waiting.get_mut(&dep).unwrap();
+            *count -= 1;
+            if *count == 0 {
+                state.waiting.remove(&dep);
+                state.ready.push_back(dep);
+            }
+        }
+        true
+    }
+
+    /// The number of running targets.
+    pub fn running(&self) -> usize {
+        self.state.lock().unwrap().running.len()
+    }
+
+    /// Whether every target added has been completed.
+    pub fn is_finished(&self) -> bool {
+        let state = self.state.lock().unwrap();
+        state.ready.is_empty() && state.running.is_empty() && state.waiting.is_empty()
+    }
+}
+
+impl<T: Eq + Hash + Clone> Default for ConcurrentDepMap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,54 @@
+//! Producer functions with access to the map.
+
+use std::hash::Hash;
+
+use crate::{CyclePolicy, DepMap, Error};
+
+/// A read-only view of a dependency map, given to producer functions.
+pub struct Context<'a, T: Eq + Hash + Clone> {
+    /// The map being worked on.
+    map: &'a DepMap<T>,
+}
+
+impl<'a, T: Eq + Hash + Clone> Context<'a, T> {
+    /// The result list so far.
+    pub fn result(&self) -> &'a [T] {
+        self.map.result()
+    }
+
+    /// The chain of active targets, from the initial one to the one being expanded.
+    ///
+    /// Each target in the chain depends on the next one.
+    pub fn chain(&self) -> impl Iterator<Item = &'a T> {
+        self.map.active_chain()
+    }
+
+    /// Whether a target is done, i.e it need not be produced.
+    pub fn is_done(&self, tgt: &T) -> bool {
+        self.map.done.contains(tgt)
+    }
+}
+
+impl<T: Eq + Hash + Clone> DepMap<T> {
+    /// Runs through a whole dependency map using a producer function with access to the map.
+    ///
+    /// Along with each target, the producer is given a [`Context`] with the result list so far
+    /// and the chain of active targets leading to it.
+    pub fn process_with_context<F, I, E>(initial: Vec<T>, mut f: F) -> Result<Vec<T>, Error<T, E>>
+    where F: FnMut(&T, Context<'_, T>) -> I, I: IntoIterator<Item = Result<T, E>> {
+        // The current map.
+        let mut state = Self::new(initial);
+        loop {
+            match state.destroy() {
+                Ok(res) => break Ok(res),
+                Err(map) => state = map,
+            };
+
+            // Not empty; Process
+            let deps = (f)(state.current().unwrap(), Context { map: &state });
+            if let Some((pos, dep)) = state.expand(|_| deps, &mut CyclePolicy::Fail)? {
+                break Err(Error::CyclicDep(state.take_cycle(pos, dep)))
+            }
+        }
+    }
+}
@@ -0,0 +1,147 @@
+//! Dominator analysis of dependency maps.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::DepMap;
+
+/// The dominator tree of the targets reachable from a root.
+///
+/// A target dominates another if every chain of dependencies from the root to the other passes
+/// through it, so dropping it would detach the other from the root. Returned by
+/// [`DepMap::dominators`].
+#[derive(Clone, Debug)]
+pub struct Dominators<'a, T> {
+    /// Every reachable target, in reverse postorder, starting with the root.
+    nodes: Vec<&'a T>,
+    /// The position of every reachable target.
+    index: HashMap<&'a T, usize>,
+    /// The position of the immediate dominator of every reachable target; the root's is itself.
+    idom: Vec<usize>,
+}
+
+impl<'a, T: Eq + Hash> Dominators<'a, T> {
+    /// The root.
+    pub fn root(&self) -> &'a T {
+        self.nodes[0]
+    }
+
+    /// The closest target dominating a target, other than itself.
+    ///
+    /// The root and unreachable targets have none.
+    pub fn immediate_dominator(&self, tgt: &T) -> Option<&'a T> {
+        match self.index.get(tgt) {
+            Some(&0) | None => None,
+            Some(&pos) => Some(self.nodes[self.idom[pos]]),
+        }
+    }
+
+    /// Every target dominating a target, other than itself, from the closest to the root.
+    pub fn dominators_of(&self, tgt: &T) -> Vec<&'a T> {
+        let mut found = Vec::new();
+        if let Some(&pos) = self.index.get(tgt) {
+            let mut pos = pos;
+            while pos != 0 {
+                pos = self.idom[pos];
+                found.push(self.nodes[pos]);
+            }
+        }
+        found
+    }
+
+    /// Whether a target dominates another.
+    ///
+    /// Every reachable target dominates itself.
+    pub fn dominates(&self, dom: &T, tgt: &T) -> bool {
+        match (self.index.get(dom), self.index.get(tgt)) {
+            (Some(&dom), Some(&tgt)) => self.dominates_pos(dom, tgt),
+            _ => false,
+        }
+    }
+
+    /// Every target a target dominates, other than itself, in reverse postorder.
+    ///
+    /// These are the targets which would be detached from the root without it.
+    pub fn dominated_by(&self, dom: &T) -> Vec<&'a T> {
+        match self.index.get(dom) {
+            Some(&dom) => (dom + 1..self.nodes.len())
+                .filter(|&pos| self.dominates_pos(dom, pos))
+                .map(|pos| self.nodes[pos])
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Whether the target at one position dominates the one at another.
+    fn dominates_pos(&self, dom: usize, mut pos: usize) -> bool {
+        // Dominators always come first in reverse postorder.
+        while pos > dom {
+            pos = self.idom[pos];
+        }
+        pos == dom
+    }
+}
+
+impl<T: Eq + Hash + Clone> DepMap<T> {
+    /// Works out the dominator tree of the targets reachable from a root, using the recorded
+    /// edges.
+    ///
+    /// Dominators are found using the Cooper-Harvey-Kennedy algorithm. Unknown roots give none.
+    pub fn dominators(&self, root: &T) -> Option<Dominators<'_, T>> {
+        let mut nodes = self.order_for(root);
+        nodes.reverse();
+        if nodes.is_empty() {
+            return None
+        }
+        let index = nodes.iter()
+            .enumerate()
+            .map(|(i, &tgt)| (tgt, i))
+            .collect::<HashMap<_, _>>();
+        // The dependents of every reachable target, by position.
+        let mut preds = vec![Vec::new(); nodes.len()];
+        for (i, &tgt) in nodes.iter().enumerate() {
+            for dep in self.recorded(tgt) {
+                preds[index[dep]].push(i);
+            }
+        }
+
+        let mut idom = vec![None; nodes.len()];
+        idom[0] = Some(0);
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for pos in 1..nodes.len() {
+                // Meet the dependents worked out so far.
+                let new = preds[pos].iter()
+                    .copied()
+                    .filter(|&pred| idom[pred].is_some())
+                    .reduce(|a, b| intersect(&idom, a, b));
+                if new.is_some() && idom[pos] != new {
+                    idom[pos] = new;
+                    changed = true;
+                }
+            }
+        }
+
+        Some(Dominators {
+            nodes,
+            index,
+            // The targets are in reverse DFS postorder, so every target but the root comes after
+            // the dependent it was reached through, and was worked out in the first pass.
+            idom: idom.into_iter().map(Option::unwrap).collect(),
+        })
+    }
+}
+
+/// The closest common dominator of two positions, given the dominators worked out so far.
+fn intersect(idom: &[Option<usize>], mut a: usize, mut b: usize) -> usize {
+    while a != b {
+        while a > b {
+            a = idom[a].unwrap();
+        }
+        while b > a {
+            b = idom[b].unwrap();
+        }
+    }
+    a
+}
@@ -0,0 +1,415 @@
+//! Graphviz export and import.
+
+use std::collections::HashSet;
+use std::fmt::{self, Write};
+use std::hash::Hash;
+
+use crate::DepMap;
+
+impl<T: Eq + Hash + Clone + fmt::Display> DepMap<T> {
+    /// Renders the map as a Graphviz DOT digraph.
+    ///
+    /// Completed targets are filled green, active targets are filled yellow and pending targets
+    /// are dashed. Edges are only drawn if the map [records them](Self::with_edges).
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph {\n");
+        // Every target, in the order it is drawn.
+        let mut seen = HashSet::new();
+        let mut nodes = Vec::new();
+        for tgt in &self.result {
+            if seen.insert(tgt) {
+                nodes.push(tgt);
+                writeln!(out, "    {} [style=filled, fillcolor=palegreen];", quote(tgt)).unwrap();
+            }
+        }
+        for tgt in self.active_chain() {
+            if seen.insert(tgt) {
+                nodes.push(tgt);
+                writeln!(out, "    {} [style=filled, fillcolor=gold];", quote(tgt)).unwrap();
+            }
+        }
+        for list in &self.list[..self.used] {
+            for tgt in &list[1..] {
+                if seen.insert(tgt) {
+                    nodes.push(tgt);
+                    writeln!(out, "    {} [style=dashed];", quote(tgt)).unwrap();
+                }
+            }
+        }
+        if let Some(edges) = &self.edges {
+            for tgt in nodes {
+                for dep in edges.get(tgt).into_iter().flatten() {
+                    writeln!(out, "    {} -> {};", quote(tgt), quote(dep)).unwrap();
+                }
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+/// Renders a target as a quoted DOT identifier.
+fn quote<T: fmt::Display>(tgt: &T) -> String {
+    let mut out = String::from("\"");
+    for c in tgt.to_string().chars() {
+        if c == '"' || c == '\\' {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out.push('"');
+    out
+}
+
+/// An error parsing a Graphviz DOT digraph.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DotError {
+    /// The line the error occurred on, starting from 1.
+    pub line: usize,
+    /// What went wrong.
+    pub message: String,
+}
+
+impl fmt::Display for DotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for DotError {}
+
+impl DepMap<String> {
+    /// Creates a new [`DepMap`] from a Graphviz DOT digraph, each edge going from a target to a
+    /// dependency.
+    ///
+    /// Attributes are ignored, and subgraphs are flattened. Every target is pending, in the order
+    /// it is first mentioned, and the edges are recorded; see [`from_edges`](Self::from_edges).
+    pub fn from_dot(src: &str) -> Result<Self, DotError> {
+        let mut parser = Parser {
+            toks: tokenize(src)?,
+            pos: 0,
+            nodes: Vec::new(),
+            edges: Vec::new(),
+        };
+        parser.graph()?;
+        Ok(Self::from_graph(parser.nodes, parser.edges))
+    }
+}
+
+/// A DOT token.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Tok {
+    /// An identifier, and whether it was quoted.
+    Id(String, bool),
+    /// A directed edge operator.
+    Arrow,
+    /// An undirected edge operator.
+    Line,
+    /// Any other punctuation.
+    Punct(char),
+}
+
+/// Splits DOT source into tokens, each with its line.
+fn tokenize(src: &str) -> Result<Vec<(Tok, usize)>, DotError> {
+    let mut toks = Vec::new();
+    let mut line = 1;
+    let mut chars = src.chars().peekable();
+    // Whether the current line has only had whitespace so far.
+    let mut line_start = true;
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => {
+                line += 1;
+                line_start = true;
+                continue;
+            },
+            c if c.is_whitespace() => continue,
+            '#' if line_start => {
+                // A preprocessor line; Skip it
+                while chars.peek().is_some_and(|&c| c != '\n') {
+                    chars.next();
+                }
+            },
+            '/' if chars.peek() == Some(&'/') => {
+                while chars.peek().is_some_and(|&c| c != '\n') {
+                    chars.next();
+                }
+            },
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let start = line;
+                let mut prev = ' ';
+                loop {
+                    match chars.next() {
+                        Some('/') if prev == '*' => break,
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            prev = c;
+                        },
+                        None => return Err(error(start, "unterminated comment")),
+                    }
+                }
+            },
+            '-' if chars.peek() == Some(&'>') => {
+                chars.next();
+                toks.push((Tok::Arrow, line));
+            },
+            '-' if chars.peek() == Some(&'-') => {
+                chars.next();
+                toks.push((Tok::Line, line));
+            },
+            '"' => {
+                let start = line;
+                let mut id = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\')) => id.push(c),
+                            // A line continuation.
+                            Some('\n') => line += 1,
+                            Some(c) => {
+                                id.push('\\');
+                                id.push(c);
+                            },
+                            None => return Err(error(start, "unterminated string")),
+                        },
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            id.push(c);
+                        },
+                        None => return Err(error(start, "unterminated string")),
+                    }
+                }
+                toks.push((Tok::Id(id, true), start));
+            },
+            '<' => {
+                // An HTML string; Keep it whole
+                let start = line;
+                let mut id = String::new();
+                let mut depth = 1;
+                loop {
+                    let c = chars.next().ok_or_else(|| error(start, "unterminated HTML string"))?;
+                    match c {
+                        '<' => depth += 1,
+                        '>' => depth -= 1,
+                        '\n' => line += 1,
+                        _ => {},
+                    }
+                    if depth == 0 {
+                        break;
+                    }
+                    id.push(c);
+                }
+                toks.push((Tok::Id(id, true), start));
+            },
+            c if c.is_alphanumeric() || c == '_' || c == '.' || c == '-' => {
+                let mut id = String::from(c);
+                while let Some(&c) = chars.peek() {
+                    if c.is_alphanumeric() || c == '_' || c == '.' {
+                        id.push(c);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                toks.push((Tok::Id(id, false), line));
+            },
+            '{' | '}' | '[' | ']' | ';' | ',' | '=' | ':' => toks.push((Tok::Punct(c), line)),
+            c => return Err(error(line, &format!("unexpected character {:?}", c))),
+        }
+        line_start = false;
+    }
+    Ok(toks)
+}
+
+/// Creates a [`DotError`].
+fn error(line: usize, message: &str) -> DotError {
+    DotError {
+        line,
+        message: message.to_string(),
+    }
+}
+
+/// A DOT parser, collecting targets and edges.
+struct Parser {
+    /// The tokens, each with its line.
+    toks: Vec<(Tok, usize)>,
+    /// The index of the next token.
+    pos: usize,
+    /// Every target, in the order it is first mentioned.
+    nodes: Vec<String>,
+    /// Every edge, from a target to a dependency.
+    edges: Vec<(String, String)>,
+}
+
+impl Parser {
+    /// Parses a whole graph.
+    fn graph(&mut self) -> Result<(), DotError> {
+        self.keyword("strict");
+        if self.keyword("graph") {
+            return Err(self.error("undirected graphs are not supported"))
+        } else if !self.keyword("digraph") {
+            return Err(self.error("expected 'digraph'"))
+        }
+        if let Some((Tok::Id(..), _)) = self.peek() {
+            self.pos += 1;
+        }
+        self.expect('{')?;
+        self.stmts()?;
+        match self.peek() {
+            None => Ok(()),
+            Some(_) => Err(self.error("expected end of input")),
+        }
+    }
+
+    /// Parses statements up to and including a closing brace, returning every target mentioned.
+    fn stmts(&mut self) -> Result<Vec<String>, DotError> {
+        let mut group = Vec::new();
+        loop {
+            match self.peek() {
+                Some((Tok::Punct('}'), _)) => {
+                    self.pos += 1;
+                    return Ok(group)
+                },
+                Some((Tok::Punct(';'), _)) => self.pos += 1,
+                Some(_) => group.extend(self.stmt()?),
+                None => return Err(self.error("expected '}'")),
+            }
+        }
+    }
+
+    /// Parses a statement, returning every target mentioned.
+    fn stmt(&mut self) -> Result<Vec<String>, DotError> {
+        if self.keyword("graph") || self.keyword("node") || self.keyword("edge") {
+            // Default attributes; Skip them
+            self.attrs()?;
+            return Ok(Vec::new())
+        }
+        if let (Some((Tok::Id(..), _)), Some((Tok::Punct('='), _)))
+            = (self.toks.get(self.pos), self.toks.get(self.pos + 1)) {
+            // A graph attribute; Skip it
+            self.pos += 2;
+            self.id()?;
+            return Ok(Vec::new())
+        }
+
+        // Targets or an edge chain.
+        let mut group = self.operand()?;
+        let mut mentioned = group.clone();
+        while let Some((Tok::Arrow | Tok::Line, _)) = self.peek() {
+            if let Some((Tok::Line, _)) = self.peek() {
+                return Err(self.error("undirected edges are not supported"))
+            }
+            self.pos += 1;
+            let next = self.operand()?;
+            for tgt in &group {
+                for dep in &next {
+                    self.edges.push((tgt.clone(), dep.clone()));
+                }
+            }
+            mentioned.extend(next.iter().cloned());
+            group = next;
+        }
+        self.attrs()?;
+        Ok(mentioned)
+    }
+
+    /// Parses a target or a subgraph, returning every target in it.
+    fn operand(&mut self) -> Result<Vec<String>, DotError> {
+        if self.keyword("subgraph") {
+            if let Some((Tok::Id(..), _)) = self.peek() {
+                self.pos += 1;
+            }
+            self.expect('{')?;
+            return self.stmts()
+        }
+        if let Some((Tok::Punct('{'), _)) = self.peek() {
+            self.pos += 1;
+            return self.stmts()
+        }
+
+        let tgt = self.id()?;
+        // Skip any port.
+        while let Some((Tok::Punct(':'), _)) = self.peek() {
+            self.pos += 1;
+            self.id()?;
+        }
+        self.nodes.push(tgt.clone());
+        Ok(vec![tgt])
+    }
+
+    /// Skips any attribute lists.
+    fn attrs(&mut self) -> Result<(), DotError> {
+        while let Some((Tok::Punct('['), _)) = self.peek() {
+            self.pos += 1;
+            loop {
+                match self.peek() {
+                    Some((Tok::Punct(']'), _)) => {
+                        self.pos += 1;
+                        break;
+                    },
+                    Some((Tok::Punct(';' | ','), _)) => self.pos += 1,
+                    Some(_) => {
+                        self.id()?;
+                        self.expect('=')?;
+                        self.id()?;
+                    },
+                    None => return Err(self.error("expected ']'")),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Parses an identifier.
+    fn id(&mut self) -> Result<String, DotError> {
+        match self.peek() {
+            Some((Tok::Id(id, _), _)) => {
+                let id = id.clone();
+                self.pos += 1;
+                Ok(id)
+            },
+            _ => Err(self.error("expected an identifier")),
+        }
+    }
+
+    /// Skips an unquoted keyword, returning whether it was found.
+    fn keyword(&mut self, kw: &str) -> bool {
+        match self.peek() {
+            Some((Tok::Id(id, false), _)) if id.eq_ignore_ascii_case(kw) => {
+                self.pos += 1;
+                true
+            },
+            _ => false,
+        }
+    }
+
+    /// Skips a punctuation token, failing if it is not found.
+    fn expect(&mut self, c: char) -> Result<(), DotError> {
+        match self.peek() {
+            Some((Tok::Punct(p), _)) if *p == c => {
+                self.pos += 1;
+                Ok(())
+            },
+            _ => Err(self.error(&format!("expected '{}'", c))),
+        }
+    }
+
+    /// The next token, if any.
+    fn peek(&self) -> Option<&(Tok, usize)> {
+        self.toks.get(self.pos)
+    }
+
+    /// Creates a [`DotError`] at the next token.
+    fn error(&self, message: &str) -> DotError {
+        let line = self.toks.get(self.pos)
+            .or_else(|| self.toks.last())
+            .map_or(1, |&(_, line)| line);
+        error(line, message)
+    }
+}
@@ -0,0 +1,280 @@
+//! Executing dependency maps.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+
+use crate::{DepMap, Error};
+
+/// A pull-based scheduler over a resolved dependency map.
+///
+/// Targets are handed out once all their dependencies have been completed, so external executors
+/// can work on them on their own schedule.
+pub struct Scheduler<T: Eq + Hash + Clone> {
+    /// The number of incomplete dependencies of every target not yet handed out.
+    waiting: HashMap<T, usize>,
+    /// The dependents of every incomplete target.
+    dependents: HashMap<T, Vec<T>>,
+    /// The targets ready to be handed out.
+    ready: VecDeque<T>,
+    /// The targets handed out but not yet completed.
+    running: HashSet<T>,
+}
+
+impl<T: Eq + Hash + Clone> Scheduler<T> {
+    /// Creates a new [`Scheduler`] from a result list and the dependencies of every target.
+    pub(crate) fn new(order: Vec<T>, deps: &HashMap<T, Vec<T>>) -> Self {
+        let mut waiting = HashMap::with_capacity(order.len());
+        let mut dependents = HashMap::<T, Vec<T>>::new();
+        for tgt in &order {
+            let uniq = deps.get(tgt).into_iter().flatten().collect::<HashSet<_>>();
+            for dep in &uniq {
+                dependents.entry((*dep).clone()).or_default().push(tgt.clone());
+            }
+            waiting.insert(tgt.clone(), uniq.len());
+        }
+        let ready = order.into_iter()
+            .filter(|tgt| waiting[tgt] == 0)
+            .collect::<VecDeque<_>>();
+        for tgt in &ready {
+            waiting.remove(tgt);
+        }
+        Self {
+            waiting,
+            dependents,
+            ready,
+            running: HashSet::new(),
+        }
+    }
+
+    /// Hands out a target whose dependencies are all complete, if any.
+    ///
+    /// The target is considered running until it is completed.
+    pub fn next_ready(&mut self) -> Option<T> {
+        let tgt = self.ready.pop_front()?;
+        self.running.insert(tgt.clone());
+        Some(tgt)
+    }
+
+    /// Hands out the first target whose dependencies are all complete and which satisfies a
+    /// predicate, if any.
+    ///
+    /// The target is considered running until it is completed.
+    pub fn next_ready_where<P: FnMut(&T) -> bool>(&mut self, pred: P) -> Option<T> {
+        let pos = self.ready.iter().position(pred)?;
+        let tgt = self.ready.remove(pos).unwrap();
+        self.running.insert(tgt.clone());
+        Some(tgt)
+    }
+
+    /// Marks a running target as complete, readying its dependents.
+    ///
+    /// Returns whether the target was running.
+    pub fn complete(&mut self, tgt: &T) -> bool {
+        if !self.running.remove(tgt) {
+            return false
+        }
+        for dep in self.dependents.remove(tgt).into_iter().flatten() {
+            let count = self.waiting.get_mut(&dep).unwrap();
+            *count -= 1;
+            if *count == 0 {
+                self.waiting.remove(&dep);
+                self.ready.push_back(dep);
+            }
+        }
+        true
+    }
+
+    /// The number of running targets.
+    pub fn running(&self) -> usize {
+        self.running.len()
+    }
+
+    /// Whether every target has been completed.
+    pub fn is_finished(&self) -> bool {
+        self.ready.is_empty() && self.running.is_empty() && self.waiting.is_empty()
+    }
+}
+
+impl<T: Eq + Hash + Clone> DepMap<T> {
+    /// Runs through a whole dependency map, returning a [`Scheduler`] over the result.
+    ///
+    /// Every target is resolved up front, before any is handed out, so the resolver's errors
+    /// are returned here rather than while working. To add targets while working, use a
+    /// [`ConcurrentDepMap`](crate::ConcurrentDepMap) instead.
+    pub fn schedule<F, I, E>(initial: Vec<T>, resolver: F) -> Result<Scheduler<T>, Error<T, E>>
+    where F: FnMut(&T) -> I, I: IntoIterator<Item = Result<T, E>> {
+        let (order, deps) = Self::process_recording(initial, resolver)?;
+        Ok(Scheduler::new(order, &deps))
+    }
+}
+
+impl<T: Eq + Hash + Clone + Send> DepMap<T> {
+    /// Runs through a whole dependency map, then works on every target on a pool of threads.
+    ///
+    /// At most `max_jobs` targets are worked on at once, and a target is only worked on once all
+    /// its dependencies are complete. The worker's results are returned in completion order.
+    ///
+    /// If the worker fails, no more targets are started, and the error is returned once the
+    /// running ones finish. If it panics, the panic is passed on.
+    pub fn run_parallel<F, I, W, R, E>(initial: Vec<T>, resolver: F, worker: W, max_jobs: usize)
+        -> Result<Vec<(T, R)>, Error<T, E>>
+    where
+        F: FnMut(&T) -> I,
+        I: IntoIterator<Item = Result<T, E>>,
+        W: Fn(&T) -> Result<R, E> + Sync,
+        R: Send,
+        E: Send,
+    {
+        Self::run_parallel_tagged(initial, resolver, worker, max_jobs, |_| Vec::<()>::new(),
+            &HashMap::new())
+    }
+
+    /// Runs through a whole dependency map, then works on every target on a pool of threads,
+    /// limiting how many targets using each resource are worked on at once.
+    ///
+    /// Every target uses the resources given by `tags`. At most `caps[tag]` targets using a
+    /// resource are worked on at once; resources without a cap are unlimited, and caps of zero
+    /// are treated as one. Otherwise, this is like [`run_parallel`](Self::run_parallel).
+    pub fn run_parallel_tagged<F, I, W, R, E, G, K>(
+        initial: Vec<T>,
+        resolver: F,
+        worker: W,
+        max_jobs: usize,
+        tags: G,
+        caps: &HashMap<K, usize>,
+    ) -> Result<Vec<(T, R)>, Error<T, E>>
+    where
+        F: FnMut(&T) -> I,
+        I: IntoIterator<Item = Result<T, E>>,
+        W: Fn(&T) -> Result<R, E> + Sync,
+        R: Send,
+        E: Send,
+        G: FnMut(&T) -> Vec<K>,
+        K: Eq + Hash,
+    {
+        let sched = Self::schedule(initial, resolver)?;
+        run_scheduled(sched, worker, max_jobs, tags, caps).map_err(Error::UserDef)
+    }
+}
+
+/// Works on every target of a scheduler on a pool of threads, limiting how many targets using
+/// each resource are worked on at once.
+///
+/// See [`DepMap::run_parallel_tagged`].
+pub(crate) fn run_scheduled<T, W, R, E, G, K>(
+    mut sched: Scheduler<T>,
+    worker: W,
+    max_jobs: usize,
+    mut tags: G,
+    caps: &HashMap<K, usize>,
+) -> Result<Vec<(T, R)>, E>
+where
+    T: Eq + Hash + Clone + Send,
+    W: Fn(&T) -> Result<R, E> + Sync,
+    R: Send,
+    E: Send,
+    G: FnMut(&T) -> Vec<K>,
+    K: Eq + Hash,
+{
+    let max_jobs = max_jobs.max(1);
+    // The resources of every target seen so far.
+    let mut uses = HashMap::<T, Vec<K>>::new();
+    // The number of running targets using each capped resource.
+    let mut in_use = HashMap::<&K, usize>::new();
+    let capped = |tags: &[K]| tags.iter()
+        .filter_map(|tag| caps.get_key_value(tag))
+        .map(|(tag, _)| tag)
+        .collect::<Vec<_>>();
+
+    let (job_tx, job_rx) = mpsc::channel::<T>();
+    let (res_tx, res_rx) = mpsc::channel();
+    let job_rx = Mutex::new(job_rx);
+    let worker = &worker;
+    let job_rx = &job_rx;
+
+    thread::scope(|scope| {
+        for _ in 0..max_jobs {
+            let res_tx = res_tx.clone();
+            scope.spawn(move || loop {
+                // Take the lock only while receiving.
+                let job = job_rx.lock().unwrap().recv();
+                match job {
+                    Ok(tgt) => {
+                        // Send panics back too, or the coordinator would wait forever.
+                        let res = panic::catch_unwind(AssertUnwindSafe(|| (worker)(&tgt)));
+                        if res_tx.send((tgt, res)).is_err() {
+                            break
+                        }
+                    },
+                    Err(_) => break,
+                }
+            });
+        }
+        drop(res_tx);
+
+        // The results, in completion order.
+        let mut results = Vec::new();
+        // The first error, if any.
+        let mut error = None;
+        loop {
+            // Start as many targets as possible.
+            while error.is_none() && sched.running() < max_jobs {
+                let next = sched.next_ready_where(|tgt| {
+                    let tags = uses.entry(tgt.clone()).or_insert_with(|| {
+                        let mut uniq = Vec::new();
+                        for tag in (tags)(tgt) {
+                            if !uniq.contains(&tag) {
+                                uniq.push(tag);
+                            }
+                        }
+                        uniq
+                    });
+                    tags.iter().all(|tag| match caps.get(tag) {
+                        Some(&cap) => in_use.get(tag).copied().unwrap_or(0) < cap.max(1),
+                        None => true,
+                    })
+                });
+                match next {
+                    Some(tgt) => {
+                        for tag in capped(&uses[&tgt]) {
+                            *in_use.entry(tag).or_default() += 1;
+                        }
+                        job_tx.send(tgt).unwrap()
+                    },
+                    None => break,
+                }
+            }
+            if sched.running() == 0 {
+                break
+            }
+
+            // Wait for one to complete.
+            let (tgt, res) = res_rx.recv().unwrap();
+            sched.complete(&tgt);
+            for tag in capped(&uses.remove(&tgt).unwrap_or_default()) {
+                *in_use.get_mut(tag).unwrap() -= 1;
+            }
+            match res {
+                Ok(Ok(res)) => results.push((tgt, res)),
+                Ok(Err(err)) => {
+                    error.get_or_insert(err);
+                },
+                Err(payload) => {
+                    // Let the workers go, then pass the panic on.
+                    drop(job_tx);
+                    panic::resume_unwind(payload)
+                },
+            }
+        }
+        // Let the workers go.
+        drop(job_tx);
+
+        match error {
+            Some(err) => Err(err),
+            None => Ok(results),
+        }
+    })
+}
@@ -0,0 +1,181 @@
+//! Structured exports.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Write};
+use std::hash::Hash;
+
+use crate::DepMap;
+
+/// The state of a target in a dependency map.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum NodeState {
+    /// In the result list.
+    Done,
+    /// Being worked on.
+    Active,
+    /// Waiting to be worked on, or only known as a recorded dependency.
+    Pending,
+}
+
+impl NodeState {
+    /// The name of the state, in lowercase.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            NodeState::Done => "done",
+            NodeState::Active => "active",
+            NodeState::Pending => "pending",
+        }
+    }
+}
+
+/// A target in an [`Export`].
+#[cfg(feature = "serde")]
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ExportNode<T> {
+    /// The index of the target in the list of nodes.
+    pub id: usize,
+    /// The target.
+    pub target: T,
+    /// The state of the target.
+    pub state: NodeState,
+}
+
+/// A recorded edge in an [`Export`], from a target to a dependency, by index.
+#[cfg(feature = "serde")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ExportEdge {
+    /// The index of the target.
+    pub from: usize,
+    /// The index of the dependency.
+    pub to: usize,
+}
+
+/// A dependency map as plain data, for consumption by external tools.
+///
+/// In JSON, this looks like:
+///
+/// ```json
+/// {
+///   "nodes": [{"id": 0, "target": "app", "state": "pending"}, ...],
+///   "edges": [{"from": 0, "to": 1}, ...],
+///   "levels": [[1], [0]]
+/// }
+/// ```
+///
+/// Nodes are every known target, in a stable order, and edges refer to them by index. Levels
+/// are as given by [`DepMap::levels`], by index, and are `null` if the recorded edges are cyclic.
+#[cfg(feature = "serde")]
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Export<T> {
+    /// Every known target.
+    pub nodes: Vec<ExportNode<T>>,
+    /// Every recorded edge.
+    pub edges: Vec<ExportEdge>,
+    /// The targets grouped into levels, if the recorded edges are acyclic.
+    pub levels: Option<Vec<Vec<usize>>>,
+}
+
+impl<T: Eq + Hash + Clone> DepMap<T> {
+    /// Exports every known target and the recorded edges as plain data.
+    ///
+    /// See [`Export`] for the format.
+    #[cfg(feature = "serde")]
+    pub fn to_export(&self) -> Export<T> {
+        let nodes = self.node_refs();
+        let pos = index(&nodes);
+        let states = self.states();
+        Export {
+            nodes: nodes.iter()
+                .enumerate()
+                .map(|(id, &tgt)| ExportNode {
+                    id,
+                    target: tgt.clone(),
+                    state: states(tgt),
+                })
+                .collect(),
+            edges: nodes.iter()
+                .enumerate()
+                .flat_map(|(from, &tgt)| self.recorded(tgt).iter()
+                    .map(move |dep| (from, dep)))
+                .map(|(from, dep)| ExportEdge { from, to: pos[dep] })
+                .collect(),
+            levels: self.levels().ok().map(|levels| levels.iter()
+                .map(|level| level.iter().map(|tgt| pos[tgt]).collect())
+                .collect()),
+        }
+    }
+
+    /// The state of every known target.
+    fn states(&self) -> impl Fn(&T) -> NodeState + '_ {
+        let done = self.result.iter().collect::<HashSet<_>>();
+        let active = self.active_chain().collect::<HashSet<_>>();
+        move |tgt| if done.contains(tgt) {
+            NodeState::Done
+        } else if active.contains(tgt) {
+            NodeState::Active
+        } else {
+            NodeState::Pending
+        }
+    }
+}
+
+impl<T: Eq + Hash + Clone + fmt::Display> DepMap<T> {
+    /// Renders every known target and the recorded edges as a GraphML document.
+    ///
+    /// Every node has a `label`, its target, and a `state`, as given by [`NodeState::as_str`].
+    pub fn to_graphml(&self) -> String {
+        let mut out = String::new();
+        out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        out.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+        for key in ["label", "state"] {
+            writeln!(
+                out,
+                "  <key id=\"{0}\" for=\"node\" attr.name=\"{0}\" attr.type=\"string\"/>",
+                key,
+            ).unwrap();
+        }
+        out.push_str("  <graph id=\"depmap\" edgedefault=\"directed\">\n");
+        let nodes = self.node_refs();
+        let pos = index(&nodes);
+        let states = self.states();
+        for (i, &tgt) in nodes.iter().enumerate() {
+            write!(out, "    <node id=\"n{}\">", i).unwrap();
+            write!(out, "<data key=\"label\">{}</data>", escape(tgt)).unwrap();
+            writeln!(out, "<data key=\"state\">{}</data></node>", states(tgt).as_str()).unwrap();
+        }
+        for (i, &tgt) in nodes.iter().enumerate() {
+            for dep in self.recorded(tgt) {
+                writeln!(out, "    <edge source=\"n{}\" target=\"n{}\"/>", i, pos[dep]).unwrap();
+            }
+        }
+        out.push_str("  </graph>\n");
+        out.push_str("</graphml>\n");
+        out
+    }
+}
+
+/// Maps every target to its index.
+fn index<'a, T: Eq + Hash>(nodes: &[&'a T]) -> HashMap<&'a T, usize> {
+    nodes.iter()
+        .enumerate()
+        .map(|(i, &tgt)| (tgt, i))
+        .collect()
+}
+
+/// Renders a target as escaped XML text.
+fn escape<T: fmt::Display>(tgt: &T) -> String {
+    let mut out = String::new();
+    for c in tgt.to_string().chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
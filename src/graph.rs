@@ -0,0 +1,549 @@
+//! Queries over recorded edges.
+
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::convert::Infallible;
+use std::hash::Hash;
+
+use crate::{components, DepMap, Error, LogEvent};
+
+impl<T: Eq + Hash + Clone> DepMap<T> {
+    /// Creates a new [`DepMap`] from a list of edges, each from a target to a dependency.
+    ///
+    /// Every target in the list is pending, and the edges are recorded, so the map can be queried
+    /// without a producer function.
+    pub fn from_edges<I: IntoIterator<Item = (T, T)>>(edges: I) -> Self {
+        Self::from_graph(None, edges)
+    }
+
+    /// Creates a new [`DepMap`] from a list of targets and a list of edges between them.
+    ///
+    /// Targets only found in edges are added after the listed ones.
+    pub fn from_graph<N, I>(nodes: N, edges: I) -> Self
+    where N: IntoIterator<Item = T>, I: IntoIterator<Item = (T, T)> {
+        let mut seen = HashSet::new();
+        let mut nodes = nodes.into_iter()
+            .filter(|node| seen.insert(node.clone()))
+            .collect::<Vec<_>>();
+        let mut deps = HashMap::<T, Vec<T>>::new();
+        let mut parents = HashMap::new();
+        for (tgt, dep) in edges {
+            for node in [&tgt, &dep] {
+                if seen.insert(node.clone()) {
+                    nodes.push(node.clone());
+                }
+            }
+            parents.entry(dep.clone()).or_insert_with(|| tgt.clone());
+            deps.entry(tgt).or_default().push(dep);
+        }
+        let mut map = Self::new(nodes);
+        map.edges = Some(deps);
+        map.parents = parents;
+        map
+    }
+
+    /// Orders every known target using the recorded edges.
+    pub fn toposort(&self) -> Result<Vec<T>, Error<T, Infallible>> {
+        Self::process(self.nodes(), |tgt| self.recorded(tgt).iter().cloned().map(Ok))
+    }
+
+    /// Finds cycles among the known targets using the recorded edges.
+    ///
+    /// See [`process_collecting`](Self::process_collecting) for which cycles are found.
+    pub fn cycles(&self) -> Vec<Vec<T>> {
+        let res = Self::process_collecting(self.nodes(), |tgt| {
+            self.recorded(tgt).iter().cloned().map(Ok::<_, Infallible>)
+        });
+        match res {
+            Ok((_, cycles)) => cycles,
+            Err(_) => unreachable!("no limits are configured"),
+        }
+    }
+
+    /// Finds every elementary cycle among the known targets using the recorded edges.
+    ///
+    /// Cycles are found using Johnson's algorithm. Each cycle is a chain of targets, each
+    /// depending on the next and the last depending on the first; it starts with whichever of
+    /// its targets is known first.
+    pub fn all_cycles(&self) -> Vec<Vec<&T>> {
+        let nodes = self.node_refs();
+        // The unique dependencies of every target, by position.
+        let adj = {
+            let pos = nodes.iter()
+                .enumerate()
+                .map(|(i, &tgt)| (tgt, i))
+                .collect::<HashMap<_, _>>();
+            nodes.iter()
+                .map(|&tgt| {
+                    let mut seen = HashSet::new();
+                    self.recorded(tgt).iter()
+                        .map(|dep| pos[dep])
+                        .filter(|&dep| seen.insert(dep))
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let mut cycles = Vec::new();
+        for start in 0..nodes.len() {
+            // Find the component of the start among it and later targets.
+            let deps = (start..nodes.len())
+                .map(|i| (i, adj[i].iter().copied().filter(|&j| j >= start).collect()))
+                .collect::<HashMap<_, Vec<_>>>();
+            let members = components((start..nodes.len()).collect(), &deps)
+                .into_iter()
+                .find(|comp| comp.contains(&start))
+                .unwrap_or_default();
+            let mut comp = vec![false; nodes.len()];
+            for i in members {
+                comp[i] = true;
+            }
+
+            // Walk paths from the start within the component, blocking dead ends.
+            let mut blocked = vec![false; nodes.len()];
+            let mut unblocks = vec![Vec::new(); nodes.len()];
+            let mut path = vec![start];
+            blocked[start] = true;
+            // Each target on the path, with the index of its next dependency and whether a cycle
+            // was found through it.
+            let mut calls = vec![(start, 0, false)];
+            while let Some(&mut (v, ref mut i, ref mut found)) = calls.last_mut() {
+                if let Some(&w) = adj[v].get(*i) {
+                    *i += 1;
+                    if !comp[w] {
+                        continue;
+                    } else if w == start {
+                        // Back at the start; a cycle
+                        cycles.push(path.iter().map(|&i| nodes[i]).collect());
+                        *found = true;
+                    } else if !blocked[w] {
+                        path.push(w);
+                        blocked[w] = true;
+                        calls.push((w, 0, false));
+                    }
+                    continue;
+                }
+
+                // Every dependency is walked; Leave the target
+                let found = *found;
+                if found {
+                    // Unblock the target, and everything waiting on it.
+                    let mut stack = vec![v];
+                    blocked[v] = false;
+                    while let Some(u) = stack.pop() {
+                        for w in std::mem::take(&mut unblocks[u]) {
+                            if blocked[w] {
+                                blocked[w] = false;
+                                stack.push(w);
+                            }
+                        }
+                    }
+                } else {
+                    // Keep the target blocked until one of its dependencies is unblocked.
+                    for &w in adj[v].iter().filter(|&&w| comp[w]) {
+                        if !unblocks[w].contains(&v) {
+                            unblocks[w].push(v);
+                        }
+                    }
+                }
+                calls.pop();
+                path.pop();
+                if let Some(call) = calls.last_mut() {
+                    call.2 |= found;
+                }
+            }
+        }
+        cycles
+    }
+
+    /// Groups every known target into levels using the recorded edges.
+    ///
+    /// See [`process_levels`](Self::process_levels) for how the levels are formed.
+    pub fn levels(&self) -> Result<Vec<Vec<T>>, Error<T, Infallible>> {
+        Self::process_levels(self.nodes(), |tgt| self.recorded(tgt).iter().cloned().map(Ok))
+    }
+
+    /// The fewest recorded edges with the same reachability, each from a target to a dependency.
+    ///
+    /// An edge is dropped if its dependency can be reached some other way. Edges are listed in
+    /// the order the targets would be processed, then the order their dependencies were produced.
+    /// Cyclic dependencies are errors, since the reduction would not be unique.
+    pub fn transitive_reduction(&self) -> Result<Vec<(T, T)>, Error<T, Infallible>> {
+        let order = self.toposort()?;
+        let pos = order.iter()
+            .enumerate()
+            .map(|(i, tgt)| (tgt, i))
+            .collect::<HashMap<_, _>>();
+        // Everything every target depends on, by position.
+        let mut reach = Vec::<HashSet<usize>>::with_capacity(order.len());
+        let mut edges = Vec::new();
+        for tgt in &order {
+            // Dependencies are processed after their own dependencies, so the nearest come last.
+            let mut deps = self.recorded(tgt).iter().map(|dep| pos[dep]).collect::<Vec<_>>();
+            deps.sort_unstable_by(|a, b| b.cmp(a));
+            deps.dedup();
+            let mut seen = HashSet::new();
+            let mut kept = HashSet::new();
+            for dep in deps {
+                if !seen.contains(&dep) {
+                    kept.insert(dep);
+                    seen.insert(dep);
+                    seen.extend(&reach[dep]);
+                }
+            }
+            for dep in self.recorded(tgt) {
+                if kept.remove(&pos[dep]) {
+                    edges.push((tgt.clone(), dep.clone()));
+                }
+            }
+            reach.push(seen);
+        }
+        Ok(edges)
+    }
+
+    /// Everything a target depends on, directly or not, using the recorded edges.
+    ///
+    /// Targets are listed breadth-first, in the order their dependencies were produced. The
+    /// target itself is only listed if it depends on itself.
+    pub fn transitive_deps(&self, tgt: &T) -> Vec<&T> {
+        reachable(tgt, |tgt| self.recorded(tgt))
+    }
+
+    /// Orders just what a target needs, ending with the target itself, using the recorded edges.
+    ///
+    /// Dependencies come in the order they were produced. Cyclic dependencies are skipped. Unknown
+    /// targets give an empty list.
+    pub fn order_for(&self, tgt: &T) -> Vec<&T> {
+        let tgt = match self.node_refs().into_iter().find(|node| *node == tgt) {
+            Some(tgt) => tgt,
+            None => return Vec::new(),
+        };
+        let mut seen = HashSet::new();
+        seen.insert(tgt);
+        let mut order = Vec::new();
+        // Walk dependencies depth-first, each target with the index of its next dependency.
+        let mut stack = vec![(tgt, 0)];
+        while let Some(&mut (node, ref mut i)) = stack.last_mut() {
+            match self.recorded(node).get(*i) {
+                Some(dep) => {
+                    *i += 1;
+                    if seen.insert(dep) {
+                        stack.push((dep, 0));
+                    }
+                },
+                None => {
+                    // Every dependency is ordered; Order the target.
+                    order.push(node);
+                    stack.pop();
+                },
+            }
+        }
+        order
+    }
+
+    /// Every target directly depending on a target, using the recorded edges.
+    pub fn dependents_of(&self, tgt: &T) -> Vec<&T> {
+        self.node_refs()
+            .into_iter()
+            .filter(|node| self.recorded(node).contains(tgt))
+            .collect()
+    }
+
+    /// The first target to depend on a target, using the recorded edges.
+    ///
+    /// Initial targets nothing depends on have no provenance.
+    pub fn provenance(&self, tgt: &T) -> Option<&T> {
+        self.parents.get(tgt)
+    }
+
+    /// Every target directly depending on a target, using the recorded edges, starting with its
+    /// [provenance](Self::provenance).
+    pub fn requesters(&self, tgt: &T) -> Vec<&T> {
+        let first = self.provenance(tgt);
+        first.into_iter()
+            .chain(self.dependents_of(tgt).into_iter().filter(|&dep| Some(dep) != first))
+            .collect()
+    }
+
+    /// Everything depending on a target, directly or not, using the recorded edges.
+    ///
+    /// Targets are listed breadth-first. The target itself is only listed if it depends on
+    /// itself.
+    pub fn transitive_dependents(&self, tgt: &T) -> Vec<&T> {
+        let dependents = self.dependents();
+        reachable(tgt, |tgt| dependents.get(tgt).into_iter().flatten().copied())
+    }
+
+    /// Every known target nothing depends on, i.e the entry points, using the recorded edges.
+    ///
+    /// Targets are listed in a stable order.
+    pub fn roots(&self) -> Vec<&T> {
+        let dependents = self.dependents();
+        self.node_refs().into_iter().filter(|tgt| !dependents.contains_key(tgt)).collect()
+    }
+
+    /// Every known target without dependencies, using the recorded edges.
+    ///
+    /// Targets which have not been expanded yet have no recorded dependencies, so they are
+    /// listed too. Targets are listed in a stable order.
+    pub fn leaves(&self) -> Vec<&T> {
+        self.node_refs().into_iter().filter(|tgt| self.recorded(tgt).is_empty()).collect()
+    }
+
+    /// The `n` known targets with the most distinct dependents, with their counts, using the
+    /// recorded edges.
+    ///
+    /// Targets are listed from the most depended upon; ties keep a stable order.
+    pub fn most_depended_upon(&self, n: usize) -> Vec<(&T, usize)> {
+        let dependents = self.dependents();
+        top(self.node_refs(), n, |tgt| dependents.get(tgt).map_or(0, Vec::len))
+    }
+
+    /// The `n` known targets with the most distinct dependencies, with their counts, using the
+    /// recorded edges.
+    ///
+    /// Targets are listed from the heaviest; ties keep a stable order.
+    pub fn heaviest_dependers(&self, n: usize) -> Vec<(&T, usize)> {
+        top(self.node_refs(), n, |tgt| {
+            self.recorded(tgt).iter().collect::<HashSet<_>>().len()
+        })
+    }
+
+    /// Finds every recorded edge from a target in a lower layer to a dependency in a higher one.
+    ///
+    /// Layers are given by a function, lower layers comparing less; targets it gives no layer are
+    /// not checked. Edges are listed once each, in a stable order.
+    pub fn layer_violations<L, F>(&self, mut layer: F) -> Vec<(&T, &T)>
+    where L: Ord, F: FnMut(&T) -> Option<L> {
+        let nodes = self.node_refs();
+        let layers = nodes.iter()
+            .filter_map(|&tgt| (layer)(tgt).map(|layer| (tgt, layer)))
+            .collect::<HashMap<_, _>>();
+        let mut seen = HashSet::new();
+        let mut found = Vec::new();
+        for tgt in nodes {
+            let from = match layers.get(tgt) {
+                Some(from) => from,
+                None => continue,
+            };
+            for dep in self.recorded(tgt) {
+                if layers.get(dep).is_some_and(|to| from < to) && seen.insert((tgt, dep)) {
+                    found.push((tgt, dep));
+                }
+            }
+        }
+        found
+    }
+
+    /// One of the shortest chains of dependencies from one target to another, using the recorded
+    /// edges.
+    ///
+    /// The chain starts with `from` and ends with `to`, each target depending on the next. If
+    /// `from` and `to` are the same, the chain is a cycle.
+    pub fn why(&self, from: &T, to: &T) -> Option<Vec<&T>> {
+        let (from, _) = self.edges.as_ref()?.get_key_value(from)?;
+        // The target every found target was found from.
+        let mut parent = HashMap::<&T, &T>::new();
+        let mut queue = VecDeque::from(vec![from]);
+        while let Some(tgt) = queue.pop_front() {
+            for dep in self.recorded(tgt) {
+                if parent.contains_key(dep) {
+                    continue;
+                }
+                parent.insert(dep, tgt);
+                if dep == to {
+                    // Walk back to the start.
+                    let mut path = vec![dep];
+                    let mut cur = tgt;
+                    while cur != from {
+                        path.push(cur);
+                        cur = parent[cur];
+                    }
+                    path.push(from);
+                    path.reverse();
+                    return Some(path)
+                }
+                queue.push_back(dep);
+            }
+        }
+        None
+    }
+
+    /// Marks a completed target as needing to be redone, along with everything that depended on
+    /// it, returning how many targets were invalidated.
+    ///
+    /// The invalidated targets are removed from the result list and added to the initial targets,
+    /// as with [`Extend`]; active targets that depended on them wait for them to be redone.
+    /// Dependents are only found if edges are [recorded](Self::with_edges).
+    pub fn invalidate(&mut self, tgt: &T) -> usize {
+        if !self.done.contains(tgt) {
+            return 0
+        }
+        if let Some(log) = &mut self.log {
+            log.push(LogEvent::Invalidated(tgt.clone()));
+        }
+
+        // Find everything that transitively depended on the target.
+        let dirty = {
+            let dependents = self.dependents();
+            let mut dirty = HashSet::new();
+            let mut stack = vec![tgt];
+            while let Some(tgt) = stack.pop() {
+                if self.done.contains(tgt) && dirty.insert(tgt.clone()) {
+                    stack.extend(dependents.get(tgt).into_iter().flatten());
+                }
+            }
+            dirty
+        };
+
+        // Take them out of the result list.
+        let redo = self.result.iter()
+            .filter(|tgt| dirty.contains(tgt))
+            .cloned()
+            .collect::<Vec<_>>();
+        self.result.retain(|tgt| !dirty.contains(tgt));
+        self.done.retain(|tgt| !dirty.contains(tgt));
+        self.dropped.retain(|tgt| !dirty.contains(tgt));
+        self.generation += 1;
+        self.last_add = None;
+        if redo.is_empty() {
+            // Only a target outside the result list was invalidated; it is not redone.
+            return dirty.len()
+        }
+
+        // Active targets already expanded wait for their invalidated dependencies again.
+        if let Some(edges) = &self.edges {
+            for i in 0..self.used.saturating_sub(1) {
+                let deps = edges.get(&self.list[i][0]).into_iter()
+                    .flatten()
+                    .filter(|dep| dirty.contains(*dep))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                self.pending += deps.len();
+                self.list[i + 1].extend(deps);
+            }
+        }
+        // Add them to the initial list, as with `Extend`.
+        if self.used == 0 {
+            self.push_used(redo);
+        } else {
+            self.pending += redo.len();
+            self.list[0].extend(redo);
+        }
+        dirty.len()
+    }
+
+    /// Whether the map records the dependencies of every target it expands.
+    ///
+    /// See [`with_edges`](Self::with_edges).
+    pub fn records_edges(&self) -> bool {
+        self.edges.is_some()
+    }
+
+    /// The recorded dependencies of a target, in the order they were produced.
+    ///
+    /// Targets which have not been expanded, or are unknown, have none.
+    pub fn deps_of(&self, tgt: &T) -> &[T] {
+        self.recorded(tgt)
+    }
+
+    /// Every known target along with its recorded dependencies, in a stable order.
+    pub fn adjacency(&self) -> impl Iterator<Item = (&T, &[T])> {
+        self.node_refs().into_iter().map(move |tgt| (tgt, self.recorded(tgt)))
+    }
+
+    /// Every recorded edge, from a target to a dependency, in a stable order.
+    ///
+    /// Dependencies produced more than once give an edge each time.
+    pub fn edges(&self) -> impl Iterator<Item = (&T, &T)> {
+        self.adjacency().flat_map(|(tgt, deps)| deps.iter().map(move |dep| (tgt, dep)))
+    }
+
+    /// The recorded dependents of every target, each in a stable order.
+    pub(crate) fn dependents(&self) -> HashMap<&T, Vec<&T>> {
+        let mut dependents = HashMap::<&T, Vec<&T>>::new();
+        for tgt in self.node_refs() {
+            for dep in self.recorded(tgt) {
+                let list = dependents.entry(dep).or_default();
+                if list.last() != Some(&tgt) {
+                    list.push(tgt);
+                }
+            }
+        }
+        dependents
+    }
+
+    /// Every known target, in a stable order.
+    ///
+    /// This is the result list, then every pending target, then any other recorded dependency.
+    pub(crate) fn nodes(&self) -> Vec<T> {
+        self.node_refs().into_iter().cloned().collect()
+    }
+
+    /// Every known target, in a stable order.
+    ///
+    /// See [`nodes`](Self::nodes).
+    pub(crate) fn node_refs(&self) -> Vec<&T> {
+        let mut seen = HashSet::new();
+        let mut nodes = Vec::new();
+        let known = self.result.iter()
+            .chain(self.list[..self.used].iter().flatten());
+        for tgt in known {
+            if seen.insert(tgt) {
+                nodes.push(tgt);
+            }
+        }
+        // Walk dependencies breadth-first, in order.
+        let mut i = 0;
+        while i < nodes.len() {
+            for dep in self.recorded(nodes[i]) {
+                if seen.insert(dep) {
+                    nodes.push(dep);
+                }
+            }
+            i += 1;
+        }
+        nodes
+    }
+
+    /// The recorded dependencies of a target.
+    pub(crate) fn recorded(&self, tgt: &T) -> &[T] {
+        self.edges.as_ref()
+            .and_then(|edges| edges.get(tgt))
+            .map_or(&[], Vec::as_slice)
+    }
+}
+
+/// The `n` targets with the highest counts, highest first, keeping ties in order.
+fn top<T, F: FnMut(&T) -> usize>(nodes: Vec<&T>, n: usize, mut count: F) -> Vec<(&T, usize)> {
+    let mut counts = nodes.into_iter()
+        .map(|tgt| (tgt, (count)(tgt)))
+        .collect::<Vec<_>>();
+    counts.sort_by_key(|&(_, count)| Reverse(count));
+    counts.truncate(n);
+    counts
+}
+
+/// Everything reachable from a target, breadth-first, given the neighbours of every target.
+///
+/// The target itself is only included if it is reachable from one of its neighbours.
+pub(crate) fn reachable<'a, T, F, I>(tgt: &T, mut next: F) -> Vec<&'a T>
+where T: Eq + Hash, F: FnMut(&T) -> I, I: IntoIterator<Item = &'a T> {
+    let mut seen = HashSet::new();
+    let mut found = Vec::new();
+    for node in (next)(tgt) {
+        if seen.insert(node) {
+            found.push(node);
+        }
+    }
+    let mut i = 0;
+    while i < found.len() {
+        for node in (next)(found[i]) {
+            if seen.insert(node) {
+                found.push(node);
+            }
+        }
+        i += 1;
+    }
+    found
+}
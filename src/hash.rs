@@ -0,0 +1,70 @@
+//! Hashing discovered graphs.
+
+use std::hash::{Hash, Hasher};
+
+use crate::{DepMap, Plan};
+
+impl<T: Eq + Hash + Clone> DepMap<T> {
+    /// A hash of every known target and recorded edge.
+    ///
+    /// The hash only depends on the graph, not on the order it was discovered in, and is stable
+    /// across runs and builds (as long as the targets hash the same way), so it can key results
+    /// cached on disk. Edges are only hashed if the map [records them](Self::with_edges).
+    pub fn graph_hash(&self) -> u64 {
+        graph_hash(self.node_refs(), |tgt| self.recorded(tgt))
+    }
+}
+
+impl<T: Eq + Hash + Clone> Plan<T> {
+    /// A hash of every target and edge.
+    ///
+    /// See [`DepMap::graph_hash`].
+    pub fn graph_hash(&self) -> u64 {
+        graph_hash(self.order(), |tgt| self.deps_of(tgt))
+    }
+}
+
+/// Hashes a graph given its targets, without duplicates, and the dependencies of every target.
+fn graph_hash<'a, T, N, F>(nodes: N, mut deps: F) -> u64
+where T: Hash + 'a, N: IntoIterator<Item = &'a T>, F: FnMut(&T) -> &'a [T] {
+    // Hash every target with its distinct dependencies, then combine them in sorted order.
+    let mut hashes = nodes.into_iter()
+        .map(|tgt| {
+            let mut dep_hashes = (deps)(tgt).iter().map(hash_one).collect::<Vec<_>>();
+            dep_hashes.sort_unstable();
+            dep_hashes.dedup();
+            hash_one(&(hash_one(tgt), dep_hashes))
+        })
+        .collect::<Vec<_>>();
+    hashes.sort_unstable();
+    hash_one(&hashes)
+}
+
+/// Hashes a value with [`Fnv`].
+pub(crate) fn hash_one<T: Hash + ?Sized>(value: &T) -> u64 {
+    let mut hasher = Fnv::default();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// The 64-bit FNV-1a hash, which unlike the standard hasher is fixed.
+struct Fnv(u64);
+
+impl Default for Fnv {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
@@ -0,0 +1,80 @@
+//! Adding initial targets while a map is being worked on.
+
+use std::hash::Hash;
+use std::mem;
+use std::sync::{Arc, Mutex};
+
+use crate::{CyclePolicy, DepMap, Error};
+
+/// A handle for adding initial targets to a map while it is being worked on.
+///
+/// Handles are cheap to clone and can be sent to other threads; every clone adds to the same map.
+pub struct Injector<T> {
+    /// Targets added but not yet taken in.
+    queue: Arc<Mutex<Vec<T>>>,
+}
+
+impl<T> Injector<T> {
+    /// Creates a new [`Injector`].
+    pub fn new() -> Self {
+        Self {
+            queue: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Adds an initial target.
+    pub fn push(&self, tgt: T) {
+        self.queue.lock().unwrap().push(tgt);
+    }
+
+    /// Adds a list of initial targets.
+    pub fn push_all<I: IntoIterator<Item = T>>(&self, tgts: I) {
+        self.queue.lock().unwrap().extend(tgts);
+    }
+
+    /// Takes every target added so far.
+    fn take(&self) -> Vec<T> {
+        mem::take(&mut *self.queue.lock().unwrap())
+    }
+}
+
+impl<T> Clone for Injector<T> {
+    fn clone(&self) -> Self {
+        Self {
+            queue: Arc::clone(&self.queue),
+        }
+    }
+}
+
+impl<T> Default for Injector<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Eq + Hash + Clone> DepMap<T> {
+    /// Runs through a whole dependency map, taking in initial targets added through an
+    /// [`Injector`] as it goes.
+    ///
+    /// The producer can hold a clone of the injector to add targets it discovers. Every target
+    /// added before this returns successfully is in the result list; see [`Extend`].
+    pub fn process_injected<F, I, E>(initial: Vec<T>, injector: &Injector<T>, mut f: F)
+        -> Result<Vec<T>, Error<T, E>>
+    where F: FnMut(&T) -> I, I: IntoIterator<Item = Result<T, E>> {
+        // The current map.
+        let mut state = Self::new(initial);
+        loop {
+            // Take in any added targets.
+            state.extend(injector.take());
+            match state.destroy() {
+                Ok(res) => break Ok(res),
+                Err(map) => state = map,
+            };
+
+            // Not empty; Process
+            if let Some((pos, dep)) = state.expand(&mut f, &mut CyclePolicy::Fail)? {
+                break Err(Error::CyclicDep(state.take_cycle(pos, dep)))
+            }
+        }
+    }
+}
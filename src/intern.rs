@@ -0,0 +1,245 @@
+//! Interned targets.
+
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::borrow::Borrow;
+use std::hash::{BuildHasher, Hash};
+use std::ops::Index;
+
+use crate::{Cycle, DepMap, Error};
+
+/// A handle to a target stored in an [`Interner`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NodeId(u32);
+
+impl NodeId {
+    /// The index of the target in its interner.
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl fmt::Display for NodeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+/// A store of targets, each kept once and referred to by a [`NodeId`].
+///
+/// Dependency maps over handles are cheap to work with, no matter how costly the targets are to
+/// compare or clone.
+pub struct Interner<T> {
+    /// Every target, by handle.
+    items: Vec<T>,
+    /// The handles of every target, by hash.
+    index: HashMap<u64, Vec<NodeId>>,
+    /// The hasher for targets.
+    hasher: RandomState,
+}
+
+impl<T: Eq + Hash> Interner<T> {
+    /// Creates a new, empty [`Interner`].
+    pub fn new() -> Self {
+        Self {
+            items: Vec::new(),
+            index: HashMap::new(),
+            hasher: RandomState::new(),
+        }
+    }
+
+    /// Stores a target if it is not already stored, returning its handle.
+    ///
+    /// # Panics
+    ///
+    /// Panics if more than `u32::MAX` targets are stored.
+    pub fn intern(&mut self, tgt: T) -> NodeId {
+        let hash = self.hasher.hash_one(&tgt);
+        let items = &self.items;
+        let ids = self.index.entry(hash).or_default();
+        if let Some(&id) = ids.iter().find(|id| items[id.index()] == tgt) {
+            return id
+        }
+        let id = NodeId(u32::try_from(self.items.len()).expect("too many targets"));
+        ids.push(id);
+        self.items.push(tgt);
+        id
+    }
+
+    /// Stores a borrowed target if it is not already stored, returning its handle.
+    ///
+    /// The target is only cloned if it is not already stored.
+    pub fn intern_ref<Q>(&mut self, tgt: &Q) -> NodeId
+    where T: Borrow<Q>, Q: ?Sized + Eq + Hash + ToOwned<Owned = T> {
+        match self.id_of(tgt) {
+            Some(id) => id,
+            None => self.intern(tgt.to_owned()),
+        }
+    }
+
+    /// The handle of a stored target, if any.
+    pub fn id_of<Q>(&self, tgt: &Q) -> Option<NodeId>
+    where T: Borrow<Q>, Q: ?Sized + Eq + Hash {
+        self.index.get(&self.hasher.hash_one(tgt))?
+            .iter()
+            .copied()
+            .find(|id| self.items[id.index()].borrow() == tgt)
+    }
+
+    /// The target a handle refers to, if any.
+    pub fn get(&self, id: NodeId) -> Option<&T> {
+        self.items.get(id.index())
+    }
+
+    /// The targets a list of handles refer to.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a handle is not from this interner.
+    pub fn resolve<'a, I: IntoIterator<Item = &'a NodeId>>(&self, ids: I) -> Vec<&T> {
+        ids.into_iter().map(|&id| &self[id]).collect()
+    }
+
+    /// The number of stored targets.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Whether no targets are stored.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Every stored target with its handle, in the order they were stored.
+    pub fn iter(&self) -> impl Iterator<Item = (NodeId, &T)> {
+        self.items.iter()
+            .enumerate()
+            .map(|(i, tgt)| (NodeId(i as u32), tgt))
+    }
+}
+
+impl<T: Eq + Hash> Default for Interner<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Index<NodeId> for Interner<T> {
+    type Output = T;
+
+    fn index(&self, id: NodeId) -> &T {
+        &self.items[id.index()]
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Interner<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(&self.items).finish()
+    }
+}
+
+impl DepMap<NodeId> {
+    /// Runs through a whole dependency map of interned targets, using a producer function over
+    /// the targets themselves.
+    ///
+    /// Every target is stored in the interner once, and the result list and errors refer to
+    /// targets by handle; look them up in the interner.
+    pub fn process_interned<U, F, I, E>(nodes: &mut Interner<U>, initial: Vec<U>, mut f: F)
+        -> Result<Vec<NodeId>, Error<NodeId, E>>
+    where U: Eq + Hash, F: FnMut(&U) -> I, I: IntoIterator<Item = Result<U, E>> {
+        let initial = initial.into_iter().map(|tgt| nodes.intern(tgt)).collect();
+        Self::process(initial, |&id| {
+            let deps = (f)(&nodes[id]).into_iter()
+                .map(|dep| dep.map(|dep| nodes.intern(dep)))
+                .collect::<Vec<_>>();
+            deps.into_iter()
+        })
+    }
+}
+
+/// A dependency map over names, interned so they are only compared once.
+///
+/// Everything is done over [`NodeId`]s, so checking whether a name is done or active compares
+/// integers instead of strings.
+#[derive(Debug)]
+pub struct StrDepMap {
+    /// Every name seen.
+    names: Interner<String>,
+    /// The map over handles.
+    map: DepMap<NodeId>,
+}
+
+impl StrDepMap {
+    /// Creates a new [`StrDepMap`] from an initial list of names.
+    pub fn new<S: AsRef<str>, N: IntoIterator<Item = S>>(initial: N) -> Self {
+        let mut names = Interner::new();
+        let initial = initial.into_iter()
+            .map(|name| names.intern_ref(name.as_ref()))
+            .collect();
+        Self {
+            names,
+            map: DepMap::new(initial),
+        }
+    }
+
+    /// Runs through a whole dependency map of names using a single producer function.
+    ///
+    /// See [`DepMap::process`].
+    pub fn process<S, N, F, I, E>(initial: N, f: F) -> Result<Vec<String>, Error<String, E>>
+    where
+        S: AsRef<str>,
+        N: IntoIterator<Item = S>,
+        F: FnMut(&str) -> I,
+        I: IntoIterator<Item = Result<S, E>>,
+    {
+        let mut map = Self::new(initial);
+        map.add_all(f)?;
+        Ok(map.result().map(str::to_string).collect())
+    }
+
+    /// Adds dependencies until the map is empty, returning cyclic dependency errors (if any).
+    ///
+    /// See [`DepMap::add_all`].
+    pub fn add_all<S, F, I, E>(&mut self, mut f: F) -> Result<(), Error<String, E>>
+    where S: AsRef<str>, F: FnMut(&str) -> I, I: IntoIterator<Item = Result<S, E>> {
+        let names = &mut self.names;
+        let res = self.map.add_all(|&id| {
+            let deps = (f)(&names[id]).into_iter()
+                .map(|dep| dep.map(|dep| names.intern_ref(dep.as_ref())))
+                .collect::<Vec<_>>();
+            deps.into_iter()
+        });
+        res.map_err(|err| match err {
+            Error::CyclicDep(cycle) => Error::CyclicDep(Cycle {
+                chain: cycle.chain.iter().map(|&id| self.names[id].clone()).collect(),
+                closed_by: self.names[cycle.closed_by].clone(),
+            }),
+            Error::UserDef(err) => Error::UserDef(err),
+            Error::LimitExceeded(limit) => Error::LimitExceeded(limit),
+            Error::Timeout(id) => Error::Timeout(self.names[id].clone()),
+        })
+    }
+
+    /// Whether the map is empty (i.e nothing needs to be worked on).
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// The result list so far.
+    pub fn result(&self) -> impl Iterator<Item = &str> {
+        self.map.result().iter().map(move |&id| self.names[id].as_str())
+    }
+
+    /// Every name seen, with its handle.
+    pub fn names(&self) -> &Interner<String> {
+        &self.names
+    }
+
+    /// The underlying map over handles.
+    pub fn map(&self) -> &DepMap<NodeId> {
+        &self.map
+    }
+}
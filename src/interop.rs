@@ -0,0 +1,40 @@
+//! Conversions to and from other graph libraries.
+
+use std::hash::Hash;
+
+use petgraph::graph::{Graph, IndexType};
+use petgraph::graphmap::DiGraphMap;
+use petgraph::visit::EdgeRef;
+use petgraph::EdgeType;
+
+use crate::DepMap;
+
+impl<N, E, Ty, Ix> From<&Graph<N, E, Ty, Ix>> for DepMap<N>
+where N: Eq + Hash + Clone, Ty: EdgeType, Ix: IndexType {
+    /// Creates a new [`DepMap`] from a graph, each edge going from a target to a dependency.
+    ///
+    /// See [`from_edges`](DepMap::from_edges).
+    fn from(graph: &Graph<N, E, Ty, Ix>) -> Self {
+        Self::from_graph(
+            graph.node_weights().cloned(),
+            graph.edge_references()
+                .map(|edge| (graph[edge.source()].clone(), graph[edge.target()].clone())),
+        )
+    }
+}
+
+impl<T: Eq + Hash + Copy + Ord> DepMap<T> {
+    /// Exports every known target and the recorded edges as a graph map.
+    ///
+    /// Each edge goes from a target to a dependency.
+    pub fn to_graphmap(&self) -> DiGraphMap<T, ()> {
+        let mut graph = DiGraphMap::new();
+        for tgt in self.nodes() {
+            graph.add_node(tgt);
+            for &dep in self.recorded(&tgt) {
+                graph.add_edge(tgt, dep, ());
+            }
+        }
+        graph
+    }
+}
@@ -0,0 +1,89 @@
+//! Isolating failures in dependency maps.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::{DepMap, Error};
+
+/// What happened to every target of a run that kept going after failures, like `make -k`.
+///
+/// Returned by [`DepMap::run_isolated`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FailureReport<T, R, E> {
+    /// The targets worked on successfully, with the worker's results, in order.
+    pub succeeded: Vec<(T, R)>,
+    /// The targets whose dependencies could not be produced or whose worker failed, with the
+    /// errors, in order.
+    pub failed: Vec<(T, E)>,
+    /// The targets not worked on, with the failed target they (transitively) depend on, in
+    /// order.
+    pub skipped: Vec<(T, T)>,
+}
+
+impl<T, R, E> FailureReport<T, R, E> {
+    /// Whether every target succeeded.
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+impl<T: Eq + Hash + Clone> DepMap<T> {
+    /// Runs through a whole dependency map, then works on every target in order, keeping going
+    /// after failures.
+    ///
+    /// When the producer or the worker fails for a target, it is marked failed, and everything
+    /// depending on it is skipped; independent targets are still resolved and worked on. Only
+    /// cyclic dependencies and exceeded limits are errors.
+    pub fn run_isolated<F, I, W, R, E>(initial: Vec<T>, mut resolver: F, mut worker: W)
+        -> Result<FailureReport<T, R, E>, Error<T, E>>
+    where F: FnMut(&T) -> I, I: IntoIterator<Item = Result<T, E>>, W: FnMut(&T) -> Result<R, E> {
+        // The producer's errors.
+        let mut errors = HashMap::new();
+        let (order, deps) = Self::process_recording(initial, |tgt| {
+            let mut list = Vec::new();
+            for dep in (resolver)(tgt) {
+                match dep {
+                    Ok(dep) => list.push(Ok(dep)),
+                    Err(err) => {
+                        errors.insert(tgt.clone(), err);
+                        list.clear();
+                        break
+                    },
+                }
+            }
+            list.into_iter()
+        })?;
+
+        let mut report = FailureReport {
+            succeeded: Vec::new(),
+            failed: Vec::new(),
+            skipped: Vec::new(),
+        };
+        // The failed target responsible for every failed or skipped target.
+        let mut causes = HashMap::<T, T>::new();
+        for tgt in order {
+            if let Some(err) = errors.remove(&tgt) {
+                causes.insert(tgt.clone(), tgt.clone());
+                report.failed.push((tgt, err));
+                continue;
+            }
+            let cause = deps.get(&tgt).into_iter()
+                .flatten()
+                .find_map(|dep| causes.get(dep))
+                .cloned();
+            if let Some(cause) = cause {
+                causes.insert(tgt.clone(), cause.clone());
+                report.skipped.push((tgt, cause));
+                continue;
+            }
+            match (worker)(&tgt) {
+                Ok(res) => report.succeeded.push((tgt, res)),
+                Err(err) => {
+                    causes.insert(tgt.clone(), tgt.clone());
+                    report.failed.push((tgt, err));
+                },
+            }
+        }
+        Ok(report)
+    }
+}
@@ -0,0 +1,166 @@
+//! Iterating over dependency maps.
+
+use std::collections::VecDeque;
+#[cfg(feature = "futures")]
+use std::future::Future;
+use std::hash::Hash;
+#[cfg(feature = "futures")]
+use std::pin::Pin;
+#[cfg(feature = "futures")]
+use std::task::{Context, Poll};
+
+use crate::{CyclePolicy, DepMap, Error};
+
+/// An iterator over targets as they are completed.
+///
+/// Returned by [`DepMap::stream`] and [`DepMap::into_completions`].
+pub struct Completions<T: Eq + Hash + Clone, F> {
+    /// The map being worked on.
+    map: DepMap<T>,
+    /// The producer function.
+    f: F,
+    /// Completed targets not yet yielded.
+    buf: VecDeque<T>,
+    /// Whether an error has been yielded.
+    failed: bool,
+}
+
+/// A stream of targets as they are completed, using an asynchronous producer function.
+///
+/// Returned by [`DepMap::stream_async`].
+#[cfg(feature = "futures")]
+pub struct AsyncCompletions<T: Eq + Hash + Clone, F, Fut> {
+    /// The map being worked on.
+    map: DepMap<T>,
+    /// The producer function.
+    f: F,
+    /// The dependencies being produced, if any.
+    fut: Option<Pin<Box<Fut>>>,
+    /// Completed targets not yet yielded.
+    buf: VecDeque<T>,
+    /// Whether an error has been yielded.
+    failed: bool,
+}
+
+// Nothing is ever pinned in place; the future is boxed.
+#[cfg(feature = "futures")]
+impl<T: Eq + Hash + Clone, F, Fut> Unpin for AsyncCompletions<T, F, Fut> {}
+
+impl<T: Eq + Hash + Clone> DepMap<T> {
+    /// Runs through a whole dependency map lazily, yielding targets as they are completed.
+    ///
+    /// Completed targets are handed out instead of being kept in a result list. Iteration stops
+    /// after the first error.
+    pub fn stream<F, I, E>(initial: Vec<T>, f: F) -> Completions<T, F>
+    where F: FnMut(&T) -> I, I: IntoIterator<Item = Result<T, E>> {
+        Self::new(initial).into_completions(f)
+    }
+
+    /// Pairs the map with a producer function, yielding targets as they are completed.
+    ///
+    /// Targets the map has already completed are yielded first. This keeps the map's options, so
+    /// maps from a [builder](Self::builder) or a [checkpoint](Self::resume) can be worked on
+    /// lazily; see [`stream`](Self::stream).
+    pub fn into_completions<F, I, E>(mut self, f: F) -> Completions<T, F>
+    where F: FnMut(&T) -> I, I: IntoIterator<Item = Result<T, E>> {
+        Completions {
+            buf: self.result.drain(..).collect(),
+            map: self,
+            f,
+            failed: false,
+        }
+    }
+
+    /// Runs through a whole dependency map lazily using an asynchronous producer function,
+    /// yielding targets as they are completed.
+    ///
+    /// See [`stream`](Self::stream) and [`process_async`](Self::process_async).
+    #[cfg(feature = "futures")]
+    pub fn stream_async<F, Fut, I, E>(initial: Vec<T>, f: F) -> AsyncCompletions<T, F, Fut>
+    where F: FnMut(&T) -> Fut, Fut: Future<Output = Result<I, E>>, I: IntoIterator<Item = T> {
+        AsyncCompletions {
+            map: Self::new(initial),
+            f,
+            fut: None,
+            buf: VecDeque::new(),
+            failed: false,
+        }
+    }
+}
+
+impl<T, F, I, E> Iterator for Completions<T, F>
+where T: Eq + Hash + Clone, F: FnMut(&T) -> I, I: IntoIterator<Item = Result<T, E>> {
+    type Item = Result<T, Error<T, E>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(tgt) = self.buf.pop_front() {
+                return Some(Ok(tgt))
+            }
+            if self.failed || self.map.is_empty() {
+                return None
+            }
+
+            // Nothing completed yet; Process
+            match self.map.expand(&mut self.f, &mut CyclePolicy::Fail) {
+                Ok(None) => self.buf.extend(self.map.result.drain(..)),
+                Ok(Some((pos, dep))) => {
+                    self.failed = true;
+                    return Some(Err(Error::CyclicDep(self.map.take_cycle(pos, dep))))
+                },
+                Err(err) => {
+                    self.failed = true;
+                    return Some(Err(err))
+                },
+            }
+        }
+    }
+}
+
+#[cfg(feature = "futures")]
+impl<T, F, Fut, I, E> futures_core::Stream for AsyncCompletions<T, F, Fut>
+where
+    T: Eq + Hash + Clone,
+    F: FnMut(&T) -> Fut,
+    Fut: Future<Output = Result<I, E>>,
+    I: IntoIterator<Item = T>,
+{
+    type Item = Result<T, Error<T, E>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(tgt) = this.buf.pop_front() {
+                return Poll::Ready(Some(Ok(tgt)))
+            }
+            if this.failed || this.map.is_empty() {
+                return Poll::Ready(None)
+            }
+
+            // Nothing completed yet; Fetch the dependencies, then process
+            let map = &this.map;
+            let f = &mut this.f;
+            let fut = this.fut.get_or_insert_with(|| Box::pin((f)(map.current().unwrap())));
+            let deps = match fut.as_mut().poll(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(deps) => deps,
+            };
+            this.fut = None;
+            let res = match deps {
+                Ok(deps) => this.map.expand(|_| deps.into_iter().map(Ok), &mut CyclePolicy::Fail),
+                Err(err) => Err(Error::UserDef(err)),
+            };
+            match res {
+                Ok(None) => this.buf.extend(this.map.result.drain(..)),
+                Ok(Some((pos, dep))) => {
+                    this.failed = true;
+                    return Poll::Ready(Some(Err(Error::CyclicDep(this.map.take_cycle(pos, dep)))))
+                },
+                Err(err) => {
+                    this.failed = true;
+                    return Poll::Ready(Some(Err(err)))
+                },
+            }
+        }
+    }
+}
@@ -0,0 +1,89 @@
+//! Dependency maps of values identified by keys.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::{DepMap, Error};
+
+/// A dependency map of values identified by keys.
+///
+/// Cyclic dependencies and duplicates are found by comparing keys, so values need not be
+/// comparable or cheap to clone. When several equal-keyed values are produced, the first is kept.
+/// Created by [`DepMap::with_key`].
+pub struct KeyedDepMap<K: Eq + Hash + Clone, V, G> {
+    /// The map over keys.
+    map: DepMap<K>,
+    /// The values, by key.
+    values: HashMap<K, V>,
+    /// The key function.
+    key: G,
+}
+
+impl<K: Eq + Hash + Clone> DepMap<K> {
+    /// Creates a new [`KeyedDepMap`] from an initial list of values and a key function.
+    pub fn with_key<V, G>(initial: Vec<V>, mut key: G) -> KeyedDepMap<K, V, G>
+    where G: FnMut(&V) -> K {
+        let mut values = HashMap::new();
+        let initial = initial.into_iter()
+            .map(|val| {
+                let k = (key)(&val);
+                values.entry(k.clone()).or_insert(val);
+                k
+            })
+            .collect();
+        KeyedDepMap {
+            map: DepMap::new(initial),
+            values,
+            key,
+        }
+    }
+}
+
+impl<K: Eq + Hash + Clone, V, G: FnMut(&V) -> K> KeyedDepMap<K, V, G> {
+    /// Adds dependencies until the map is empty, returning cyclic dependency errors (if any).
+    ///
+    /// See [`DepMap::add_all`]. Errors refer to values by key.
+    pub fn add_all<F, I, E>(&mut self, mut f: F) -> Result<(), Error<K, E>>
+    where F: FnMut(&V) -> I, I: IntoIterator<Item = Result<V, E>> {
+        let values = &mut self.values;
+        let key = &mut self.key;
+        self.map.add_all(|k| {
+            let deps = (f)(&values[k]).into_iter()
+                .map(|dep| dep.map(|val| {
+                    let k = (key)(&val);
+                    values.entry(k.clone()).or_insert(val);
+                    k
+                }))
+                .collect::<Vec<_>>();
+            deps.into_iter()
+        })
+    }
+
+    /// Whether the map is empty (i.e nothing needs to be worked on).
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// The result list so far.
+    pub fn result(&self) -> impl Iterator<Item = &V> {
+        self.map.result().iter().map(move |k| &self.values[k])
+    }
+
+    /// The value with a key, if it has been seen.
+    pub fn get(&self, k: &K) -> Option<&V> {
+        self.values.get(k)
+    }
+
+    /// The underlying map over keys.
+    pub fn map(&self) -> &DepMap<K> {
+        &self.map
+    }
+
+    /// Takes the values in the result list, in order.
+    pub fn into_result(mut self) -> Vec<V> {
+        let values = &mut self.values;
+        self.map.result.drain(..)
+            .map(|k| values.remove(&k).unwrap())
+            .collect()
+    }
+}
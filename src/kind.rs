@@ -0,0 +1,65 @@
+//! Labeled dependencies.
+
+use std::cell::Cell;
+use std::hash::Hash;
+
+use crate::{CyclePolicy, DepMap, Error, Resolution};
+
+/// The kind of a dependency.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EdgeKind {
+    /// Needed to run the target.
+    Run,
+    /// Needed to build the target.
+    Build,
+    /// Needed to develop (e.g test) the target.
+    Dev,
+    /// Not needed, but used if available.
+    Optional,
+}
+
+/// How strongly a target depends on a dependency.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Strength {
+    /// The dependency is required.
+    Strong,
+    /// The dependency is followed, but dropped if it would close a cycle.
+    Weak,
+}
+
+impl<T: Eq + Hash + Clone> DepMap<T> {
+    /// Runs through a whole dependency map using a producer function giving labeled dependencies,
+    /// only following dependencies of the given kinds.
+    pub fn process_filtered<F, I, E>(initial: Vec<T>, kinds: &[EdgeKind], mut f: F)
+        -> Result<Vec<T>, Error<T, E>>
+    where F: FnMut(&T) -> I, I: IntoIterator<Item = Result<(T, EdgeKind), E>> {
+        Self::process(initial, |tgt| (f)(tgt).into_iter().filter_map(|dep| match dep {
+            Ok((dep, kind)) => if kinds.contains(&kind) {Some(Ok(dep))} else {None},
+            Err(err) => Some(Err(err)),
+        }))
+    }
+
+    /// Runs through a whole dependency map using a producer function giving dependencies with
+    /// their strength.
+    ///
+    /// Weak dependencies are followed like any other, but are skipped if they would close a
+    /// cycle. Cycles closed by strong dependencies are still errors, even if they pass through
+    /// weak ones.
+    pub fn process_weak<F, I, E>(initial: Vec<T>, mut f: F) -> Result<Vec<T>, Error<T, E>>
+    where F: FnMut(&T) -> I, I: IntoIterator<Item = Result<(T, Strength), E>> {
+        // The strength of the latest dependency produced.
+        let last = Cell::new(Strength::Strong);
+        let policy = CyclePolicy::callback(|_| match last.get() {
+            Strength::Strong => Resolution::Fail,
+            Strength::Weak => Resolution::SkipEdge,
+        });
+        Self::process_with(initial, policy, |tgt| {
+            (f)(tgt).into_iter().map(|dep| dep.map(|(dep, strength)| {
+                last.set(strength);
+                dep
+            }))
+        })
+    }
+}
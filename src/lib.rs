@@ -4,13 +4,267 @@
 //! stuff.
 //!
 //! Cyclic dependencies are found and handled.
+//!
+//! ## Features
+//!
+//! - `cargo`: `DepMap::from_cargo_metadata`, building maps of packages from `cargo_metadata`.
+//! - `cli`: the `depmap` command-line tool, ordering graphs read from standard input.
+//! - `derive`: `#[derive(Dependencies)]`, implementing [`DependencyProvider`].
+//! - `futures`: `DepMap::stream_async`, a `Stream` of targets as they are completed.
+//! - `petgraph`: conversions from `petgraph` graphs and into `petgraph` graph maps.
+//! - `persist`: `Cache`, storing resolved plans in a file between runs.
+//! - `pipeline`: `DepMap::pipeline`, handing out targets and taking completions over
+//!   `crossbeam-channel` channels.
+//! - `rayon`: `DepMap::par_process`, producing dependencies in parallel.
+//! - `serde`: `Serialize` and `Deserialize` for [`DepMap`], [`Checkpoint`], [`EventLog`] and
+//!   [`Error`], so resolutions can be persisted, resumed and replayed, and `DepMap::to_export`, a
+//!   documented plain data form of the graph.
+//! - `testutil`: `TestGraph`, seeded generators of graphs for testing producer functions. With
+//!   `quickcheck` or `proptest`, it implements their `Arbitrary` traits.
+//! - `tokio`: `DepMap::run_tokio`, spawning a `tokio` task for every target once it is ready.
+//! - `tracing`: `DepMap::process_traced`, emitting `tracing` spans and events.
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::fmt;
+use std::future::Future;
+use std::hash::Hash;
+use std::iter::FromIterator;
+use std::ops::ControlFlow;
+
+mod batch;
+mod builder;
+mod cache;
+mod checkpoint;
+mod closure;
+mod concurrent;
+mod context;
+mod dominators;
+mod dot;
+mod exec;
+mod export;
+mod graph;
+mod hash;
+mod inject;
+mod intern;
+mod isolate;
+#[cfg(feature = "petgraph")]
+mod interop;
+mod iter;
+mod keyed;
+mod kind;
+mod log;
+mod merge;
+mod mermaid;
+#[cfg(feature = "cargo")]
+mod metadata;
+#[cfg(feature = "serde")]
+mod pairs;
+#[cfg(feature = "rayon")]
+mod par;
+mod partition;
+#[cfg(feature = "persist")]
+mod persist;
+#[cfg(feature = "pipeline")]
+mod pipeline;
+mod plan;
+mod provider;
+mod retry;
+mod snapshot;
+mod stats;
+mod step;
+#[cfg(feature = "tokio")]
+mod task;
+#[cfg(feature = "testutil")]
+mod testutil;
+mod timeout;
+mod timing;
+#[cfg(feature = "tracing")]
+mod trace;
+mod validate;
+mod verify;
+mod visit;
+mod watch;
+
+pub use builder::{DepMapBuilder, ProcessBuilder};
+pub use cache::CachedResolver;
+pub use checkpoint::{Checkpoint, UnknownVersion};
+pub use closure::Closure;
+pub use concurrent::ConcurrentDepMap;
+pub use context::Context;
+pub use dominators::Dominators;
+pub use dot::DotError;
+pub use exec::Scheduler;
+pub use export::NodeState;
+#[cfg(feature = "serde")]
+pub use export::{Export, ExportEdge, ExportNode};
+pub use inject::Injector;
+pub use intern::{Interner, NodeId, StrDepMap};
+pub use isolate::FailureReport;
+pub use iter::Completions;
+#[cfg(feature = "futures")]
+pub use iter::AsyncCompletions;
+pub use keyed::KeyedDepMap;
+pub use kind::{EdgeKind, Strength};
+pub use log::{EventLog, LogEvent};
+pub use merge::MergeError;
+pub use partition::Shared;
+#[cfg(feature = "persist")]
+pub use persist::Cache;
+#[cfg(feature = "pipeline")]
+pub use pipeline::Pipeline;
+pub use plan::{ExecOptions, Plan, PlanDiff};
+pub use provider::DependencyProvider;
+pub use retry::RetryPolicy;
+pub use snapshot::{Snapshot, StaleSnapshot};
+pub use stats::GraphStats;
+pub use step::StepOutcome;
+#[cfg(feature = "tokio")]
+pub use task::{OnFailure, TaskLimits};
+#[cfg(feature = "testutil")]
+pub use testutil::TestGraph;
+pub use timing::{CriticalPath, Schedule, Slot};
+#[cfg(feature = "derive")]
+pub use depmap_derive::Dependencies;
+pub use validate::Violation;
+pub use verify::{verify, VerifyError};
+pub use visit::{DepVisitor, Event};
+pub use watch::Watcher;
 
 /// An error type.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Error<T, E> {
     /// A cyclic dependency error.
-    CyclicDep(Vec<T>),
+    CyclicDep(Cycle<T>),
     /// A user-defined error.
+    ///
+    /// It is displayed as is, and its source is the error's own source.
     UserDef(E),
+    /// A configured limit was exceeded.
+    LimitExceeded(Limit),
+    /// Working on a target took too long.
+    Timeout(T),
+}
+
+/// A cyclic dependency.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cycle<T> {
+    /// The chain of active targets in the cycle, each depending on the next.
+    pub chain: Vec<T>,
+    /// The dependency of the last target in the chain that closed the cycle.
+    ///
+    /// This is equal to the first target in the chain.
+    pub closed_by: T,
+}
+
+impl<T> Cycle<T> {
+    /// The edge that closed the cycle, from a target to its dependency.
+    pub fn closing_edge(&self) -> (&T, &T) {
+        (self.chain.last().unwrap_or(&self.closed_by), &self.closed_by)
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Cycle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Name the closing edge, then render the chain, closing the loop.
+        let (tgt, dep) = self.closing_edge();
+        write!(f, "{} depends on {}: ", tgt, dep)?;
+        for tgt in &self.chain {
+            write!(f, "{} -> ", tgt)?;
+        }
+        write!(f, "{}", self.closed_by)
+    }
+}
+
+/// A user-defined error, along with the target it occurred for.
+///
+/// Only the target and chain are displayed; the error is the source.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Failure<T, E> {
+    /// The target whose dependencies were being produced.
+    pub target: T,
+    /// The chain of active targets requiring the target, from the initial one on.
+    pub chain: Vec<T>,
+    /// The error.
+    pub error: E,
+}
+
+impl<T: fmt::Display, E> fmt::Display for Failure<T, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "while resolving {}", self.target)?;
+        if let Some((first, rest)) = self.chain.split_first() {
+            write!(f, " (required by {}", first)?;
+            for tgt in rest {
+                write!(f, " -> {}", tgt)?;
+            }
+            write!(f, ")")?;
+        }
+        Ok(())
+    }
+}
+
+impl<T, E> std::error::Error for Failure<T, E>
+where T: fmt::Debug + fmt::Display, E: std::error::Error + 'static {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// An error, along with everything worked out before it occurred.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Partial<T, E> {
+    /// The error.
+    pub error: Error<T, E>,
+    /// The result list so far.
+    pub done: Vec<T>,
+    /// The targets that were still pending, active ones first.
+    pub pending: Vec<T>,
+}
+
+impl<T: fmt::Display, E: fmt::Display> fmt::Display for Partial<T, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.error, f)
+    }
+}
+
+impl<T, E> std::error::Error for Partial<T, E>
+where T: fmt::Debug + fmt::Display, E: std::error::Error + 'static {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        std::error::Error::source(&self.error)
+    }
+}
+
+/// A limit on the size of a dependency map.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Limit {
+    /// The maximum number of active targets, i.e the length of the longest dependency chain.
+    Depth(usize),
+    /// The maximum number of distinct targets completed or pending.
+    Nodes(usize),
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Limit::Depth(max) => write!(f, "dependency chain longer than {} targets", max),
+            Limit::Nodes(max) => write!(f, "more than {} distinct targets", max),
+        }
+    }
+}
+
+/// Configuration for a dependency map.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DepMapConfig {
+    /// The maximum number of active targets, i.e the length of the longest dependency chain.
+    pub max_depth: Option<usize>,
+    /// The maximum number of distinct targets completed or pending.
+    pub max_nodes: Option<usize>,
 }
 
 impl<T, E> From<E> for Error<T, E> {
@@ -19,8 +273,88 @@ impl<T, E> From<E> for Error<T, E> {
     }
 }
 
+impl<T, E> Error<T, E> {
+    /// Converts the user-defined error, if any, keeping any other error.
+    fn map_user<F, G: FnOnce(E) -> F>(self, f: G) -> Error<T, F> {
+        match self {
+            Error::CyclicDep(cycle) => Error::CyclicDep(cycle),
+            Error::UserDef(err) => Error::UserDef((f)(err)),
+            Error::LimitExceeded(limit) => Error::LimitExceeded(limit),
+            Error::Timeout(tgt) => Error::Timeout(tgt),
+        }
+    }
+}
+
+impl<T: fmt::Display, E: fmt::Display> fmt::Display for Error<T, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::CyclicDep(cycle) => write!(f, "cyclic dependency: {}", cycle),
+            Error::UserDef(err) => fmt::Display::fmt(err, f),
+            Error::LimitExceeded(limit) => write!(f, "limit exceeded: {}", limit),
+            Error::Timeout(tgt) => write!(f, "timed out working on {}", tgt),
+        }
+    }
+}
+
+impl<T, E> std::error::Error for Error<T, E>
+where T: fmt::Debug + fmt::Display, E: std::error::Error + 'static {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::CyclicDep(_) | Error::LimitExceeded(_) | Error::Timeout(_) => None,
+            Error::UserDef(err) => err.source(),
+        }
+    }
+}
+
+/// What to do about a cyclic dependency.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Resolution {
+    /// Fail with a cyclic dependency error.
+    Fail,
+    /// Skip the dependency that closes the cycle.
+    SkipEdge,
+    /// Drop the target whose dependency closes the cycle.
+    ///
+    /// The target is treated as done, but is left out of the result list.
+    DropNode,
+}
+
+/// How cyclic dependencies are handled.
+#[allow(clippy::type_complexity)]
+pub enum CyclePolicy<'a, T> {
+    /// Fail with a cyclic dependency error.
+    Fail,
+    /// Skip the dependency that closes the cycle.
+    SkipEdge,
+    /// Drop the target whose dependency closes the cycle.
+    DropNode,
+    /// Decide with a callback, given the chain of active targets in the cycle.
+    Callback(Box<dyn FnMut(&[T]) -> Resolution + 'a>),
+}
+
+impl<'a, T> CyclePolicy<'a, T> {
+    /// Creates a [`CyclePolicy::Callback`] from a closure.
+    pub fn callback<F: FnMut(&[T]) -> Resolution + 'a>(f: F) -> Self {
+        CyclePolicy::Callback(Box::new(f))
+    }
+
+    /// Decides what to do about a cycle, given its chain of active targets.
+    fn resolve<'b>(&mut self, chain: impl FnOnce() -> Vec<&'b T>) -> Resolution
+    where T: Clone + 'b {
+        match self {
+            CyclePolicy::Fail => Resolution::Fail,
+            CyclePolicy::SkipEdge => Resolution::SkipEdge,
+            CyclePolicy::DropNode => Resolution::DropNode,
+            CyclePolicy::Callback(f) => (f)(&chain().into_iter().cloned().collect::<Vec<_>>()),
+        }
+    }
+}
+
 /// The dependency map.
-pub struct DepMap<T: PartialEq> {
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DepMap<T: Eq + Hash + Clone> {
     /// A list of lists of things that need to be worked on at the same level.
     /// The first of each list is 'active'; the others will be handled in reverse order.
     /// The last few lists might be empty, called free lists.
@@ -29,23 +363,265 @@ pub struct DepMap<T: PartialEq> {
     result: Vec<T>,
     /// The number of used lists.
     used: usize,
+    /// Everything done (in the result list or dropped), for quick lookup.
+    done: HashSet<T>,
+    /// The active targets, mapped to the index of their list.
+    #[cfg_attr(feature = "serde", serde(with = "crate::pairs"))]
+    active: HashMap<T, usize>,
+    /// The dependencies of every expanded target, if they are being recorded.
+    #[cfg_attr(feature = "serde", serde(with = "crate::pairs::option"))]
+    edges: Option<HashMap<T, Vec<T>>>,
+    /// The first target to depend on every recorded dependency.
+    #[cfg_attr(feature = "serde", serde(with = "crate::pairs"))]
+    parents: HashMap<T, T>,
+    /// Whether lists are handled in order, rather than first then in reverse order.
+    stable: bool,
+    /// The number of targets in used lists.
+    pending: usize,
+    /// The configuration.
+    config: DepMapConfig,
+    /// The targets dropped while processing, i.e done but left out of the result list.
+    #[cfg_attr(feature = "serde", serde(default))]
+    dropped: Vec<T>,
+    /// The number of times targets were taken out of the result list or added to the map from
+    /// elsewhere, which invalidates snapshots.
+    #[cfg_attr(feature = "serde", serde(default))]
+    generation: usize,
+    /// Whether [`add`](Self::add) calls can be undone.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    undo: bool,
+    /// A snapshot from before the latest [`add`](Self::add) call, if it can be undone.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    last_add: Option<Snapshot<T>>,
+    /// The log of what has happened to the map, if it is being logged.
+    #[cfg_attr(feature = "serde", serde(default))]
+    log: Option<EventLog<T>>,
+}
+
+impl<T: Eq + Hash + Clone + fmt::Debug> fmt::Debug for DepMap<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DepMap")
+            .field("active", &self.active_chain().collect::<Vec<_>>())
+            .field("pending", &self.list[..self.used].iter()
+                .map(|list| &list[1..])
+                .collect::<Vec<_>>())
+            .field("result", &self.result)
+            .finish()
+    }
+}
+
+impl<T: Eq + Hash + Clone> PartialEq for DepMap<T> {
+    /// Compares the pending lists, done targets, recorded edges and provenance, and configuration
+    /// of two maps.
+    ///
+    /// Free lists are not compared.
+    fn eq(&self, other: &Self) -> bool {
+        self.list[..self.used] == other.list[..other.used]
+            && self.result == other.result
+            && self.done == other.done
+            && self.edges == other.edges
+            && self.parents == other.parents
+            && self.stable == other.stable
+            && self.config == other.config
+    }
+}
+
+impl<T: Eq + Hash + Clone> Eq for DepMap<T> {}
+
+impl<T: Eq + Hash + Clone> FromIterator<T> for DepMap<T> {
+    /// Creates a new [`DepMap`] from an initial list.
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self::new(iter.into_iter().collect())
+    }
 }
 
-impl<T: PartialEq> DepMap<T> {
+impl<T: Eq + Hash + Clone> Extend<T> for DepMap<T> {
+    /// Adds targets to the initial list, even while the map is being worked on.
+    ///
+    /// Targets already done are skipped. The rest are handled along with the remaining initial
+    /// targets, once the current active chain is completed. Adding any invalidates snapshots.
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let done = &self.done;
+        let roots = iter.into_iter()
+            .filter(|tgt| !done.contains(tgt))
+            .collect::<Vec<_>>();
+        if roots.is_empty() {
+            return
+        }
+        if let Some(log) = &mut self.log {
+            log.push(LogEvent::Added(roots.clone()));
+        }
+        self.generation += 1;
+        self.last_add = None;
+        if self.used == 0 {
+            // Nothing being worked on; Start over from the new targets
+            self.push_used(roots);
+        } else {
+            // The first used list holds the initial targets.
+            self.pending += roots.len();
+            self.list[0].extend(roots);
+        }
+    }
+}
+
+impl<T: Eq + Hash + Clone> DepMap<T> {
     /// Creates a new [`DepMap`] from an initial list.
     pub fn new(list: Vec<T>) -> Self {
+        let mut active = HashMap::new();
+        if let Some(tgt) = list.first() {
+            active.insert(tgt.clone(), 0);
+        }
         Self {
             used: if list.is_empty() {0} else {1},
+            pending: list.len(),
             list: vec![list],
             result: Vec::new(),
+            done: HashSet::new(),
+            active,
+            edges: None,
+            parents: HashMap::new(),
+            stable: false,
+            config: DepMapConfig::default(),
+            dropped: Vec::new(),
+            generation: 0,
+            undo: false,
+            last_add: None,
+            log: None,
+        }
+    }
+
+    /// Creates a new [`DepMap`] from an initial list and a list of targets already done.
+    ///
+    /// Targets already done are treated as satisfied, but are left out of the result list.
+    pub fn with_done<I: IntoIterator<Item = T>>(mut list: Vec<T>, done: I) -> Self {
+        let done = done.into_iter().collect::<HashSet<_>>();
+        list.retain(|tgt| !done.contains(tgt));
+        let mut map = Self::new(list);
+        map.done = done;
+        map
+    }
+
+    /// Makes the map record the dependencies of every target it expands.
+    pub fn with_edges(mut self) -> Self {
+        self.edges.get_or_insert_with(HashMap::new);
+        self
+    }
+
+    /// Sets the configuration of the map.
+    pub fn with_config(mut self, config: DepMapConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Makes the map handle the dependencies of each target in the order they were produced.
+    ///
+    /// This makes the result list deterministic, at some cost for targets with many dependencies.
+    pub fn with_stable_order(mut self) -> Self {
+        self.stable = true;
+        self
+    }
+
+    /// Starts over with a new initial list, keeping the configuration and allocated memory.
+    pub fn reset<I: IntoIterator<Item = T>>(&mut self, list: I) {
+        for list in &mut self.list {
+            list.clear();
+        }
+        self.used = 0;
+        self.pending = 0;
+        self.result.clear();
+        self.done.clear();
+        self.active.clear();
+        self.dropped.clear();
+        self.generation += 1;
+        self.last_add = None;
+        if let Some(edges) = &mut self.edges {
+            edges.clear();
+        }
+        self.parents.clear();
+
+        let mut free = self.get_free();
+        free.extend(list);
+        if free.is_empty() {
+            self.list.push(free);
+        } else {
+            self.push_used(free);
+        }
+        if self.log.is_some() {
+            self.restart_log();
         }
     }
 
     /// Runs through a whole dependency map using a single producer function.
     ///
     /// This is probably what one should use.
-    pub fn process<F, I, E>(initial: Vec<T>, mut f: F) -> Result<Vec<T>, Error<T, E>>
-    where F: FnMut(&T) -> I, I: Iterator<Item = Result<T, E>> {
+    pub fn process<F, I, E>(initial: Vec<T>, f: F) -> Result<Vec<T>, Error<T, E>>
+    where F: FnMut(&T) -> I, I: IntoIterator<Item = Result<T, E>> {
+        Self::process_with(initial, CyclePolicy::Fail, f)
+    }
+
+    /// Runs through a whole dependency map, returning the result list reversed.
+    ///
+    /// Dependents come before their dependencies, as needed for teardown: e.g services stopped in
+    /// the opposite order they were started.
+    pub fn process_reversed<F, I, E>(initial: Vec<T>, f: F) -> Result<Vec<T>, Error<T, E>>
+    where F: FnMut(&T) -> I, I: IntoIterator<Item = Result<T, E>> {
+        let mut res = Self::process(initial, f)?;
+        res.reverse();
+        Ok(res)
+    }
+
+    /// Runs through a whole dependency map, handling cyclic dependencies using the given policy.
+    pub fn process_with<F, I, E>(initial: Vec<T>, mut policy: CyclePolicy<T>, f: F)
+        -> Result<Vec<T>, Error<T, E>>
+    where F: FnMut(&T) -> I, I: IntoIterator<Item = Result<T, E>> {
+        Self::new(initial).finish(&mut policy, f)
+    }
+
+    /// Runs through a whole dependency map, keeping independent targets in the order they were
+    /// produced.
+    ///
+    /// See [`with_stable_order`](Self::with_stable_order).
+    pub fn process_stable<F, I, E>(initial: Vec<T>, f: F) -> Result<Vec<T>, Error<T, E>>
+    where F: FnMut(&T) -> I, I: IntoIterator<Item = Result<T, E>> {
+        Self::new(initial).with_stable_order().finish(&mut CyclePolicy::Fail, f)
+    }
+
+    /// Runs through a whole dependency map, attaching to user-defined errors the target whose
+    /// dependencies were being produced and the chain of targets requiring it.
+    ///
+    /// Dependencies are taken from the producer one at a time, so it can fail part-way through
+    /// (e.g while parsing a manifest); the first error stops everything.
+    pub fn process_fallible<F, I, E>(initial: Vec<T>, mut f: F)
+        -> Result<Vec<T>, Error<T, Failure<T, E>>>
+    where F: FnMut(&T) -> I, I: IntoIterator<Item = Result<T, E>> {
+        // The current map.
+        let mut state = Self::new(initial);
+        loop {
+            match state.destroy() {
+                Ok(res) => break Ok(res),
+                Err(map) => state = map,
+            };
+
+            // Not empty; Process
+            match state.expand(&mut f, &mut CyclePolicy::Fail) {
+                Ok(None) => {},
+                Ok(Some((pos, dep))) => break Err(Error::CyclicDep(state.take_cycle(pos, dep))),
+                Err(err) => break Err(err.map_user(|error| {
+                    // The target is still the latest active one.
+                    let mut chain = state.active_chain().cloned().collect::<Vec<_>>();
+                    let target = chain.pop().unwrap();
+                    Failure { target, chain, error }
+                })),
+            }
+        }
+    }
+
+    /// Runs through a whole dependency map using a producer function that can prune targets.
+    ///
+    /// If the producer breaks, the target is treated as already done: its dependencies are never
+    /// explored, and it is left out of the result list.
+    pub fn process_pruning<F, I, E>(initial: Vec<T>, mut f: F) -> Result<Vec<T>, Error<T, E>>
+    where F: FnMut(&T) -> ControlFlow<(), I>, I: IntoIterator<Item = Result<T, E>> {
         // The current map.
         let mut state = Self::new(initial);
         loop {
@@ -54,18 +630,139 @@ impl<T: PartialEq> DepMap<T> {
                 Err(map) => state = map,
             };
 
+            // Not empty; Prune or process
+            match (f)(state.current().unwrap()) {
+                ControlFlow::Break(()) => state.drop_cur(false),
+                ControlFlow::Continue(deps) => {
+                    if let Some((pos, dep)) = state.expand(|_| deps, &mut CyclePolicy::Fail)? {
+                        break Err(Error::CyclicDep(state.take_cycle(pos, dep)))
+                    }
+                },
+            }
+        }
+    }
+
+    /// Runs through the rest of the map, handling cyclic dependencies using the given policy.
+    fn finish<F, I, E>(self, policy: &mut CyclePolicy<T>, mut f: F) -> Result<Vec<T>, Error<T, E>>
+    where F: FnMut(&T) -> I, I: IntoIterator<Item = Result<T, E>> {
+        // The current map.
+        let mut state = self;
+        loop {
+            match state.destroy() {
+                Ok(res) => break Ok(res),
+                Err(map) => state = map,
+            };
+
             // Not empty; Process
-            state.add(&mut f)?
-                .map(|deps| deps.len())
-                .map_or(Ok(()), |len| Err(state.list.iter_mut()
-                    .take(state.used)
-                    .skip(state.used - len)
-                    .map(|list| list.swap_remove(0))
-                    .collect::<Vec<_>>()))
-                .map_err(Error::CyclicDep)?;
+            if let Some((pos, dep)) = state.expand(&mut f, policy)? {
+                break Err(Error::CyclicDep(state.take_cycle(pos, dep)))
+            }
+        }
+    }
+
+    /// Runs through a whole dependency map, failing if the configured limits are exceeded.
+    pub fn process_limited<F, I, E>(initial: Vec<T>, config: DepMapConfig, f: F)
+        -> Result<Vec<T>, Error<T, E>>
+    where F: FnMut(&T) -> I, I: IntoIterator<Item = Result<T, E>> {
+        Self::new(initial).with_config(config).finish(&mut CyclePolicy::Fail, f)
+    }
+
+    /// Runs through a whole dependency map, keeping everything worked out if an error occurs.
+    pub fn process_partial<F, I, E>(initial: Vec<T>, f: F) -> Result<Vec<T>, Partial<T, E>>
+    where F: FnMut(&T) -> I, I: IntoIterator<Item = Result<T, E>> {
+        let mut map = Self::new(initial);
+        match map.add_all(f) {
+            Ok(()) => Ok(map.result),
+            Err(error) => {
+                let mut seen = HashSet::new();
+                let others = map.list[..map.used].iter().flat_map(|list| &list[1..]);
+                let pending = map.active_chain().chain(others)
+                    .filter(|tgt| !map.done.contains(*tgt) && seen.insert(*tgt))
+                    .cloned()
+                    .collect();
+                Err(Partial { error, done: map.result, pending })
+            },
         }
     }
 
+    /// Runs through a whole dependency map, collecting user-defined errors instead of failing.
+    ///
+    /// When the producer function fails for a target, the target is treated as having no
+    /// dependencies, and it and everything depending on it are left out of the result list.
+    /// Independent targets are still worked on. The errors are returned with the result list.
+    #[allow(clippy::type_complexity)]
+    pub fn process_aggregating<F, I, E>(initial: Vec<T>, mut f: F)
+        -> Result<(Vec<T>, Vec<(T, E)>), Error<T, E>>
+    where F: FnMut(&T) -> I, I: IntoIterator<Item = Result<T, E>> {
+        // The errors so far.
+        let mut errors = Vec::new();
+        let (order, deps) = Self::process_recording(initial, |tgt| {
+            let mut list = Vec::new();
+            for dep in (f)(tgt) {
+                match dep {
+                    Ok(dep) => list.push(Ok(dep)),
+                    Err(err) => {
+                        errors.push((tgt.clone(), err));
+                        list.clear();
+                        break
+                    },
+                }
+            }
+            list.into_iter()
+        })?;
+
+        // Fail everything depending on a failed target.
+        let mut failed = errors.iter()
+            .map(|(tgt, _)| tgt.clone())
+            .collect::<HashSet<_>>();
+        let order = order.into_iter()
+            .filter(|tgt| {
+                if failed.contains(tgt) || deps[tgt].iter().any(|dep| failed.contains(dep)) {
+                    failed.insert(tgt.clone());
+                    false
+                } else {
+                    true
+                }
+            })
+            .collect();
+        Ok((order, errors))
+    }
+
+    /// Runs through a whole dependency map using an asynchronous producer function.
+    ///
+    /// Only one target is expanded at a time.
+    pub async fn process_async<F, Fut, I, E>(initial: Vec<T>, mut f: F)
+        -> Result<Vec<T>, Error<T, E>>
+    where F: FnMut(&T) -> Fut, Fut: Future<Output = Result<I, E>>, I: IntoIterator<Item = T> {
+        // The current map.
+        let mut state = Self::new(initial);
+        loop {
+            match state.destroy() {
+                Ok(res) => break Ok(res),
+                Err(map) => state = map,
+            };
+
+            // Not empty; Fetch the dependencies, then process
+            let deps = (f)(state.current().unwrap()).await?;
+            let res = state.expand(|_| deps.into_iter().map(Ok::<T, E>), &mut CyclePolicy::Fail)?;
+            if let Some((pos, dep)) = res {
+                break Err(Error::CyclicDep(state.take_cycle(pos, dep)))
+            }
+        }
+    }
+
+    /// The result list so far.
+    pub fn result(&self) -> &[T] {
+        &self.result
+    }
+
+    /// The chain of active targets, from the initial one to the one being expanded next.
+    ///
+    /// Each target in the chain depends on the next one.
+    pub fn active_chain(&self) -> impl Iterator<Item = &T> {
+        self.list[..self.used].iter().map(|list| &list[0])
+    }
+
     /// Whether the map is empty (i.e nothing needs to be worked on).
     pub fn is_empty(&self) -> bool {
         self.used == 0
@@ -74,6 +771,7 @@ impl<T: PartialEq> DepMap<T> {
     /// Returns the result list if the dependency map is empty.
     ///
     /// If it is not empty, then an error is returned with the whole map.
+    #[allow(clippy::result_large_err)]
     pub fn destroy(self) -> Result<Vec<T>, Self> {
         if self.is_empty() {
             Ok(self.result)
@@ -82,32 +780,329 @@ impl<T: PartialEq> DepMap<T> {
         }
     }
 
+    /// Runs through a whole dependency map, grouping the result into levels.
+    ///
+    /// Everything in a level only depends on things in earlier levels, so the targets of each
+    /// level can be worked on in parallel.
+    pub fn process_levels<F, I, E>(initial: Vec<T>, f: F) -> Result<Vec<Vec<T>>, Error<T, E>>
+    where F: FnMut(&T) -> I, I: IntoIterator<Item = Result<T, E>> {
+        let (order, deps) = Self::process_recording(initial, f)?;
+        Ok(levels(order, &deps))
+    }
+
+    /// Runs through a whole dependency map, pairing every target with its depth.
+    ///
+    /// A target's depth is the length of its longest chain of dependencies, so targets without
+    /// any are at depth 0; see [`process_levels`](Self::process_levels).
+    pub fn process_with_depth<F, I, E>(initial: Vec<T>, f: F)
+        -> Result<Vec<(T, usize)>, Error<T, E>>
+    where F: FnMut(&T) -> I, I: IntoIterator<Item = Result<T, E>> {
+        let (order, deps) = Self::process_recording(initial, f)?;
+        let depths = depths(&order, &deps);
+        Ok(order.into_iter().zip(depths).collect())
+    }
+
+    /// Runs through a whole dependency map, ordering targets using a comparator.
+    ///
+    /// Whenever several targets have all their dependencies done, the least one (according to the
+    /// comparator) comes first. Ties are broken by the order [`process`](Self::process) would
+    /// give. For example, passing [`Ord::cmp`] gives the lexicographically smallest order.
+    pub fn process_by<F, I, E, C>(initial: Vec<T>, f: F, cmp: C) -> Result<Vec<T>, Error<T, E>>
+    where F: FnMut(&T) -> I, I: IntoIterator<Item = Result<T, E>>, C: FnMut(&T, &T) -> Ordering {
+        let (order, deps) = Self::process_recording(initial, f)?;
+        Ok(order_by(order, &deps, cmp))
+    }
+
+    /// Runs through a whole dependency map, also mapping every target to its position in the
+    /// result list.
+    ///
+    /// Whether one target comes before another can then be checked without searching.
+    #[allow(clippy::type_complexity)]
+    pub fn process_indexed<F, I, E>(initial: Vec<T>, f: F)
+        -> Result<(Vec<T>, HashMap<T, usize>), Error<T, E>>
+    where F: FnMut(&T) -> I, I: IntoIterator<Item = Result<T, E>> {
+        let order = Self::process(initial, f)?;
+        let index = order.iter()
+            .enumerate()
+            .map(|(i, tgt)| (tgt.clone(), i))
+            .collect();
+        Ok((order, index))
+    }
+
+    /// Runs through a whole dependency map, mapping every target to an output as it is completed.
+    ///
+    /// The mapping is called in dependency order, as soon as each target is completed, so outputs
+    /// (e.g compiled objects or checksums) can be made without a second pass over the result.
+    pub fn process_map<F, I, E, G, R>(initial: Vec<T>, mut f: F, mut g: G)
+        -> Result<Vec<(T, R)>, Error<T, E>>
+    where F: FnMut(&T) -> I, I: IntoIterator<Item = Result<T, E>>, G: FnMut(&T) -> R {
+        // The completed targets, with their outputs.
+        let mut out = Vec::new();
+        // The current map.
+        let mut state = Self::new(initial);
+        while !state.is_empty() {
+            if let Some((pos, dep)) = state.expand(&mut f, &mut CyclePolicy::Fail)? {
+                return Err(Error::CyclicDep(state.take_cycle(pos, dep)))
+            }
+            // Map anything completed.
+            out.extend(state.result.drain(..).map(|tgt| {
+                let res = (g)(&tgt);
+                (tgt, res)
+            }));
+        }
+        Ok(out)
+    }
+
+    /// Runs through a whole dependency map, folding an accumulator over targets as they are
+    /// completed.
+    ///
+    /// Targets are folded in dependency order and never collected, so aggregates (e.g a total
+    /// size or a combined hash) need no result list.
+    pub fn process_fold<F, I, E, G, A>(initial: Vec<T>, mut f: F, init: A, mut g: G)
+        -> Result<A, Error<T, E>>
+    where F: FnMut(&T) -> I, I: IntoIterator<Item = Result<T, E>>, G: FnMut(A, &T) -> A {
+        // The accumulator so far.
+        let mut acc = init;
+        // The current map.
+        let mut state = Self::new(initial);
+        while !state.is_empty() {
+            if let Some((pos, dep)) = state.expand(&mut f, &mut CyclePolicy::Fail)? {
+                return Err(Error::CyclicDep(state.take_cycle(pos, dep)))
+            }
+            // Fold anything completed.
+            acc = state.result.drain(..).fold(acc, |acc, tgt| (g)(acc, &tgt));
+        }
+        Ok(acc)
+    }
+
+    /// Runs through a whole dependency map, grouping cyclic dependencies together instead of
+    /// failing.
+    ///
+    /// Every group is a strongly connected component: its targets all depend on each other, so
+    /// they must be worked on together. Groups are in dependency order, and the targets of each
+    /// group are in the order [`process`](Self::process) would give.
+    pub fn process_condensed<F, I, E>(initial: Vec<T>, f: F) -> Result<Vec<Vec<T>>, Error<T, E>>
+    where F: FnMut(&T) -> I, I: IntoIterator<Item = Result<T, E>> {
+        let (order, deps) = Self::process_recording_with(initial, CyclePolicy::SkipEdge, f)?;
+        Ok(components(order, &deps))
+    }
+
+    /// Runs through a whole dependency map, recording the dependencies of every target.
+    #[allow(clippy::type_complexity)]
+    fn process_recording<F, I, E>(initial: Vec<T>, f: F)
+        -> Result<(Vec<T>, HashMap<T, Vec<T>>), Error<T, E>>
+    where F: FnMut(&T) -> I, I: IntoIterator<Item = Result<T, E>> {
+        Self::process_recording_with(initial, CyclePolicy::Fail, f)
+    }
+
+    /// Runs through a whole dependency map using the given cycle policy, recording the
+    /// dependencies of every target.
+    #[allow(clippy::type_complexity)]
+    fn process_recording_with<F, I, E>(initial: Vec<T>, policy: CyclePolicy<T>, mut f: F)
+        -> Result<(Vec<T>, HashMap<T, Vec<T>>), Error<T, E>>
+    where F: FnMut(&T) -> I, I: IntoIterator<Item = Result<T, E>> {
+        // The dependencies of every target.
+        let mut deps = HashMap::new();
+        let order = Self::process_with(initial, policy, |tgt| {
+            let list = (f)(tgt).into_iter().collect::<Vec<_>>();
+            deps.insert(tgt.clone(), list.iter()
+                .filter_map(|dep| dep.as_ref().ok().cloned())
+                .collect());
+            list.into_iter()
+        })?;
+        Ok((order, deps))
+    }
+
+    /// Runs through a whole dependency map of values identified by keys.
+    ///
+    /// Cyclic dependencies and duplicates are found by comparing keys, so values need not be
+    /// comparable or cheap to clone. When several equal-keyed values are produced, the first is
+    /// kept. See [`with_key`](Self::with_key).
+    pub fn process_keyed<V, G, F, I, E>(initial: Vec<V>, key: G, f: F)
+        -> Result<Vec<V>, Error<T, E>>
+    where G: FnMut(&V) -> T, F: FnMut(&V) -> I, I: IntoIterator<Item = Result<V, E>> {
+        let mut map = Self::with_key(initial, key);
+        map.add_all(f)?;
+        Ok(map.into_result())
+    }
+
+    /// Runs through a whole dependency map, collecting cyclic dependencies instead of failing.
+    ///
+    /// Dependencies that would close a cycle are skipped, and the chain of active targets they
+    /// would have closed is recorded. The (partial) result list is returned with every cycle
+    /// found.
+    #[allow(clippy::type_complexity)]
+    pub fn process_collecting<F, I, E>(initial: Vec<T>, mut f: F)
+        -> Result<(Vec<T>, Vec<Vec<T>>), Error<T, E>>
+    where F: FnMut(&T) -> I, I: IntoIterator<Item = Result<T, E>> {
+        // The current map.
+        let mut state = Self::new(initial);
+        // The cycles found so far.
+        let mut cycles = Vec::new();
+        loop {
+            match state.destroy() {
+                Ok(res) => break Ok((res, cycles)),
+                Err(map) => state = map,
+            };
+
+            // Not empty; Process
+            cycles.extend(state.add_skipping(&mut f)?);
+        }
+    }
+
     /// Adds the latest target's dependencies at the end, removing those already done and
     /// returning cyclic dependency errors (if any).
     ///
-    /// When cyclic dependency errors occur, the target is retained but its dependencies are not.
+    /// When cyclic dependency errors occur, the target is retained but its dependencies are not;
+    /// the same goes for other errors.
     /// Skips everything if the depmap is empty.
-    pub fn add<F, I, E>(&mut self, f: F) -> Result<Option<Vec<&T>>, E>
-    where F: FnOnce(&T) -> I, I: Iterator<Item = Result<T, E>> {
+    ///
+    /// Since 0.1, errors are wrapped in [`Error`], so exceeded limits can be reported; the
+    /// producer's errors are [`Error::UserDef`].
+    pub fn add<F, I, E>(&mut self, f: F) -> Result<Option<Vec<&T>>, Error<T, E>>
+    where F: FnOnce(&T) -> I, I: IntoIterator<Item = Result<T, E>> {
+        self.save_undo();
+        match self.expand(f, &mut CyclePolicy::Fail)? {
+            Some((pos, _)) => {
+                Ok(Some(self.list[pos..self.used].iter().map(|list| &list[0]).collect()))
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Adds dependencies until the map is empty, returning cyclic dependency errors (if any).
+    ///
+    /// Unlike [`process`](Self::process), the map is kept, so it can be inspected afterwards.
+    /// When errors occur, the map is left as [`add`](Self::add) leaves it.
+    pub fn add_all<F, I, E>(&mut self, mut f: F) -> Result<(), Error<T, E>>
+    where F: FnMut(&T) -> I, I: IntoIterator<Item = Result<T, E>> {
+        self.last_add = None;
+        while !self.is_empty() {
+            if let Some((pos, dep)) = self.expand(&mut f, &mut CyclePolicy::Fail)? {
+                return Err(Error::CyclicDep(Cycle {
+                    chain: self.list[pos..self.used].iter().map(|list| list[0].clone()).collect(),
+                    closed_by: dep,
+                }))
+            }
+        }
+        Ok(())
+    }
+
+    /// Like [`add`](Self::add), but skips dependencies that would cause cyclic dependency errors.
+    ///
+    /// The chains of active targets that would have been reported are returned instead.
+    pub fn add_skipping<F, I, E>(&mut self, f: F) -> Result<Vec<Vec<T>>, Error<T, E>>
+    where F: FnOnce(&T) -> I, I: IntoIterator<Item = Result<T, E>> {
+        self.save_undo();
+        let mut cycles = Vec::new();
+        self.expand(f, &mut CyclePolicy::callback(|chain: &[T]| {
+            cycles.push(chain.to_vec());
+            Resolution::SkipEdge
+        }))?;
+        Ok(cycles)
+    }
+
+    /// Adds the latest target's dependencies at the end, handling cycles using the given policy.
+    ///
+    /// If the policy fails, the position of the first active target in the cycle is returned,
+    /// along with the dependency that closed it.
+    fn expand<F, I, E>(&mut self, f: F, policy: &mut CyclePolicy<T>)
+        -> Result<Option<(usize, T)>, Error<T, E>>
+    where F: FnOnce(&T) -> I, I: IntoIterator<Item = Result<T, E>> {
+        let (target, pos) = match (self.current(), &self.log) {
+            (Some(tgt), Some(log)) => (tgt.clone(), log.len()),
+            _ => return self.expand_unlogged(f, policy),
+        };
+
+        // Log the dependencies as they are produced, before anything they cause.
+        let mut deps = Vec::new();
+        let mut failed = false;
+        let res = self.expand_unlogged(|tgt| (f)(tgt).into_iter().inspect(|dep| match dep {
+            Ok(dep) => deps.push(dep.clone()),
+            Err(_) => failed = true,
+        }), policy);
+        if let Some(log) = &mut self.log {
+            log.insert(pos, LogEvent::Expanded { target, deps, failed });
+        }
+        res
+    }
+
+    /// Like [`expand`](Self::expand), but without logging the expansion.
+    fn expand_unlogged<F, I, E>(&mut self, f: F, policy: &mut CyclePolicy<T>)
+        -> Result<Option<(usize, T)>, Error<T, E>>
+    where F: FnOnce(&T) -> I, I: IntoIterator<Item = Result<T, E>> {
         if self.is_empty() {
             return Ok(None);
         }
 
         // Get a free list.
         let mut free = self.get_free();
+        // The distinct targets pending but not done, if the number of targets is limited.
+        let done = &self.done;
+        let mut known = self.config.max_nodes.map(|_| self.list[..self.used].iter()
+            .flatten()
+            .filter(|tgt| !done.contains(tgt))
+            .cloned()
+            .collect::<HashSet<_>>());
+        // Get the list of recorded dependencies, if any.
+        let cur = &self.list[self.used - 1][0];
+        let mut deps = self.edges.as_mut().map(|edges| {
+            let deps = edges.entry(cur.clone()).or_default();
+            deps.clear();
+            deps
+        });
         // Add to it the new targets.
-        for tgt in (f)(&self.list[self.used - 1][0]) {
+        for tgt in (f)(cur) {
             let tgt = tgt?;
-            if self.result.iter().any(|done| done == &tgt) {
+            if let Some(deps) = deps.as_mut() {
+                deps.push(tgt.clone());
+                self.parents.entry(tgt.clone()).or_insert_with(|| cur.clone());
+            }
+            if self.done.contains(&tgt) {
                 // Found in result list; already done, skip
                 continue;
-            } else if let Some(pos) = self.list[0..self.used].iter()
-                    .map(|list| &list[0]).position(|cur| cur == &tgt) {
-                // Found in active target list; cyclic dependency, fail
-                free.clear();
-                self.list.push(free);
-                return Ok(Some(self.list[pos..self.used].iter().map(|list| &list[0]).collect()))
+            } else if let Some(&pos) = self.active.get(&tgt) {
+                // Found in active target list; cyclic dependency
+                let used = self.used;
+                let list = &self.list;
+                let resolution = policy.resolve(|| {
+                    list[pos..used].iter().map(|list| &list[0]).collect()
+                });
+                if let Some(log) = &mut self.log {
+                    log.push(LogEvent::Cycle {
+                        chain: list[pos..used].iter().map(|list| list[0].clone()).collect(),
+                        resolution,
+                    });
+                }
+                match resolution {
+                    // Skip the dependency
+                    Resolution::SkipEdge => continue,
+                    // Drop the target along with its dependencies
+                    Resolution::DropNode => {
+                        free.clear();
+                        self.list.push(free);
+                        self.drop_cur(false);
+                        return Ok(None)
+                    },
+                    // Fail
+                    Resolution::Fail => {
+                        free.clear();
+                        self.list.push(free);
+                        return Ok(Some((pos, tgt)))
+                    },
+                }
             } else {
+                if let Some(known) = known.as_mut().filter(|known| !known.contains(&tgt)) {
+                    // Not seen yet; check the limit
+                    let max_nodes = self.config.max_nodes.unwrap();
+                    if self.done.len() + known.len() >= max_nodes {
+                        // Too many targets; fail
+                        free.clear();
+                        self.list.push(free);
+                        return Err(Error::LimitExceeded(Limit::Nodes(max_nodes)))
+                    }
+                    known.insert(tgt.clone());
+                }
                 // No issues; unhandled, add to list
                 free.push(tgt)
             }
@@ -115,17 +1110,46 @@ impl<T: PartialEq> DepMap<T> {
         // If the list is empty, then the target is a node; drop active targets.
         // Otherwise, add the list to the used space.
         if free.is_empty() {
-            self.drop_cur();
-        } else {
-            // Add the free length to the used space.
-            let len = self.list.len();
+            self.drop_cur(true);
+        } else if self.config.max_depth.is_some_and(|max| self.used >= max) {
+            // Too deep; fail
+            free.clear();
             self.list.push(free);
-            self.list.swap(len, self.used);
-            self.used += 1;
+            return Err(Error::LimitExceeded(Limit::Depth(self.config.max_depth.unwrap())))
+        } else {
+            self.push_used(free);
         }
         Ok(None)
     }
 
+    /// Adds a non-empty list to the used space, making its first target active.
+    fn push_used(&mut self, list: Vec<T>) {
+        self.active.insert(list[0].clone(), self.used);
+        self.pending += list.len();
+        let len = self.list.len();
+        self.list.push(list);
+        self.list.swap(len, self.used);
+        self.used += 1;
+    }
+
+    /// Returns the latest active target, if any.
+    fn current(&self) -> Option<&T> {
+        self.list[..self.used].last().map(|list| &list[0])
+    }
+
+    /// Takes the active targets out of every used list from the given position on, as a cycle
+    /// closed by the given dependency.
+    ///
+    /// The map is left inconsistent, so this is only for building errors.
+    fn take_cycle(&mut self, pos: usize, closed_by: T) -> Cycle<T> {
+        Cycle {
+            chain: self.list[pos..self.used].iter_mut()
+                .map(|list| list.swap_remove(0))
+                .collect(),
+            closed_by,
+        }
+    }
+
     /// Returns a free list.
     fn get_free(&mut self) -> Vec<T> {
         if self.used < self.list.len() {
@@ -138,13 +1162,27 @@ impl<T: PartialEq> DepMap<T> {
     }
 
     /// Drops as many active targets as possible, beginning from the end.
-    fn drop_cur(&mut self) {
+    ///
+    /// If `keep` is false, the latest active target is marked done but left out of the result list.
+    fn drop_cur(&mut self, mut keep: bool) {
         // While used lengths exist:
         while self.used > 0 {
             // Get the latest used list.
             let list = &mut self.list[self.used - 1];
             // Drop the active target into the result list.
-            self.result.push(list.swap_remove(0));
+            let tgt = take_first(list, self.stable);
+            self.pending -= 1;
+            self.active.remove(&tgt);
+            self.done.insert(tgt.clone());
+            if keep {
+                if let Some(log) = &mut self.log {
+                    log.push(LogEvent::Completed(tgt.clone()));
+                }
+                self.result.push(tgt);
+            } else {
+                self.dropped.push(tgt);
+            }
+            keep = true;
             // While the list isn't empty, search for a target that has not been handled yet.
             let found = loop {
                 if list.is_empty() {
@@ -155,9 +1193,11 @@ impl<T: PartialEq> DepMap<T> {
 
                 // In result list: Already handled, remove and continue
                 // Otherwise: found unhandled, stop
-                if self.result.iter().any(|done| done == tgt) {
-                    list.swap_remove(0);
+                if self.done.contains(tgt) {
+                    take_first(list, self.stable);
+                    self.pending -= 1;
                 } else {
+                    self.active.insert(tgt.clone(), self.used - 1);
                     break true
                 }
             };
@@ -171,3 +1211,189 @@ impl<T: PartialEq> DepMap<T> {
         }
     }
 }
+
+/// Removes the first target of a list, keeping the others in order if `stable` is set.
+fn take_first<T>(list: &mut Vec<T>, stable: bool) -> T {
+    if stable {
+        list.remove(0)
+    } else {
+        list.swap_remove(0)
+    }
+}
+
+/// Reorders a result list using a comparator, given the dependencies of every target.
+///
+/// Whenever several targets have all their dependencies placed, the least one is placed next.
+fn order_by<T, C>(order: Vec<T>, deps: &HashMap<T, Vec<T>>, mut cmp: C) -> Vec<T>
+where T: Eq + Hash, C: FnMut(&T, &T) -> Ordering {
+    // Rank every target, breaking ties by position.
+    let mut by_rank = (0..order.len()).collect::<Vec<_>>();
+    by_rank.sort_by(|&a, &b| (cmp)(&order[a], &order[b]));
+    let mut rank = vec![0; order.len()];
+    for (r, &i) in by_rank.iter().enumerate() {
+        rank[i] = r;
+    }
+
+    // The number of unplaced dependencies and the dependents of every target, by position.
+    let pos = order.iter()
+        .enumerate()
+        .map(|(i, tgt)| (tgt, i))
+        .collect::<HashMap<_, _>>();
+    let mut waiting = vec![0; order.len()];
+    let mut dependents = vec![Vec::new(); order.len()];
+    for (i, tgt) in order.iter().enumerate() {
+        let uniq = deps.get(tgt)
+            .into_iter()
+            .flatten()
+            .filter_map(|dep| pos.get(dep).copied())
+            .collect::<HashSet<_>>();
+        waiting[i] = uniq.len();
+        for dep in uniq {
+            dependents[dep].push(i);
+        }
+    }
+
+    // Place the least ready target until none are left.
+    let mut ready = (0..order.len())
+        .filter(|&i| waiting[i] == 0)
+        .map(|i| Reverse(rank[i]))
+        .collect::<BinaryHeap<_>>();
+    let mut placed = Vec::with_capacity(order.len());
+    while let Some(Reverse(r)) = ready.pop() {
+        let i = by_rank[r];
+        placed.push(i);
+        for &dep in &dependents[i] {
+            waiting[dep] -= 1;
+            if waiting[dep] == 0 {
+                ready.push(Reverse(rank[dep]));
+            }
+        }
+    }
+
+    let mut slots = order.into_iter().map(Some).collect::<Vec<_>>();
+    placed.into_iter()
+        .map(|i| slots[i].take().unwrap())
+        .collect()
+}
+
+/// The depth of every target in a result list, given the dependencies of every target.
+///
+/// A target's depth is the length of its longest chain of dependencies, so targets without any
+/// are at depth 0. Dependencies closing cycles are not followed.
+fn depths<T: Eq + Hash>(order: &[T], deps: &HashMap<T, Vec<T>>) -> Vec<usize> {
+    let mut depth = HashMap::<&T, usize>::new();
+    order.iter()
+        .map(|tgt| {
+            let lvl = deps.get(tgt)
+                .into_iter()
+                .flatten()
+                .filter_map(|dep| depth.get(dep))
+                .map(|lvl| lvl + 1)
+                .max()
+                .unwrap_or(0);
+            depth.insert(tgt, lvl);
+            lvl
+        })
+        .collect()
+}
+
+/// Groups a result list into levels, given the dependencies of every target.
+///
+/// Each target is placed one level after its latest dependency.
+fn levels<T: Eq + Hash>(order: Vec<T>, deps: &HashMap<T, Vec<T>>) -> Vec<Vec<T>> {
+    // The level of every target, in order.
+    let lvls = depths(&order, deps);
+    let mut res = Vec::<Vec<T>>::new();
+    for (tgt, lvl) in order.into_iter().zip(lvls) {
+        if res.len() <= lvl {
+            res.resize_with(lvl + 1, Vec::new);
+        }
+        res[lvl].push(tgt);
+    }
+    res
+}
+
+/// Groups a result list into strongly connected components, given the dependencies of every
+/// target.
+///
+/// Components are found using Tarjan's algorithm, so they come in dependency order. Targets keep
+/// their relative order.
+fn components<T: Eq + Hash>(order: Vec<T>, deps: &HashMap<T, Vec<T>>) -> Vec<Vec<T>> {
+    // The dependencies of every target, by position.
+    let adj = {
+        let pos = order.iter()
+            .enumerate()
+            .map(|(i, tgt)| (tgt, i))
+            .collect::<HashMap<_, _>>();
+        order.iter()
+            .map(|tgt| deps.get(tgt)
+                .into_iter()
+                .flatten()
+                .filter_map(|dep| pos.get(dep).copied())
+                .collect::<Vec<_>>())
+            .collect::<Vec<_>>()
+    };
+
+    // The visit index and lowest reachable index of every target, by position.
+    let mut index = vec![usize::MAX; order.len()];
+    let mut low = vec![0; order.len()];
+    let mut on_stack = vec![false; order.len()];
+    let mut stack = Vec::new();
+    let mut next = 0;
+    let mut comps = Vec::new();
+    for root in 0..order.len() {
+        if index[root] != usize::MAX {
+            continue;
+        }
+        index[root] = next;
+        low[root] = next;
+        next += 1;
+        stack.push(root);
+        on_stack[root] = true;
+        // Walk dependencies depth-first, each target with the index of its next dependency.
+        let mut calls = vec![(root, 0)];
+        while let Some(&mut (v, ref mut i)) = calls.last_mut() {
+            if let Some(&w) = adj[v].get(*i) {
+                *i += 1;
+                if index[w] == usize::MAX {
+                    // Unvisited; Visit it
+                    index[w] = next;
+                    low[w] = next;
+                    next += 1;
+                    stack.push(w);
+                    on_stack[w] = true;
+                    calls.push((w, 0));
+                } else if on_stack[w] {
+                    low[v] = low[v].min(index[w]);
+                }
+                continue;
+            }
+
+            // Every dependency is visited; Finish the target
+            calls.pop();
+            if let Some(&(u, _)) = calls.last() {
+                low[u] = low[u].min(low[v]);
+            }
+            if low[v] == index[v] {
+                let mut comp = Vec::new();
+                loop {
+                    let w = stack.pop().unwrap();
+                    on_stack[w] = false;
+                    comp.push(w);
+                    if w == v {
+                        break;
+                    }
+                }
+                comp.sort_unstable();
+                comps.push(comp);
+            }
+        }
+    }
+
+    let mut order = order.into_iter().map(Some).collect::<Vec<_>>();
+    comps.into_iter()
+        .map(|comp| comp.into_iter()
+            .map(|i| order[i].take().unwrap())
+            .collect())
+        .collect()
+}
@@ -0,0 +1,136 @@
+//! Logging and replaying dependency maps.
+
+use std::hash::Hash;
+
+use crate::{Checkpoint, CyclePolicy, DepMap, Resolution, UnknownVersion};
+
+/// Something that happened to a dependency map, as logged by [`DepMap::with_log`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LogEvent<T> {
+    /// Targets were added to the initial list.
+    Added(Vec<T>),
+    /// A target was expanded, producing the given dependencies.
+    Expanded {
+        /// The target.
+        target: T,
+        /// The dependencies produced before expansion stopped.
+        deps: Vec<T>,
+        /// Whether the producer failed after them.
+        failed: bool,
+    },
+    /// A dependency closed a cycle, given its chain of active targets.
+    Cycle {
+        /// The chain of active targets in the cycle.
+        chain: Vec<T>,
+        /// How the cycle was resolved.
+        resolution: Resolution,
+    },
+    /// A target was completed.
+    Completed(T),
+    /// A target was invalidated; see [`DepMap::invalidate`].
+    Invalidated(T),
+}
+
+/// A log of everything that happened to a dependency map, from which it can be replayed.
+///
+/// Returned by [`DepMap::log`]. With the `serde` feature, this can be attached to a bug report
+/// and replayed elsewhere.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EventLog<T> {
+    /// The map when logging started.
+    start: Checkpoint<T>,
+    /// Everything that happened since, in order.
+    events: Vec<LogEvent<T>>,
+}
+
+impl<T> EventLog<T> {
+    /// Everything that happened since logging started, in order.
+    pub fn events(&self) -> &[LogEvent<T>] {
+        &self.events
+    }
+
+    /// Logs an event.
+    pub(crate) fn push(&mut self, event: LogEvent<T>) {
+        self.events.push(event);
+    }
+
+    /// The number of events logged.
+    pub(crate) fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Forgets events from a position on.
+    pub(crate) fn truncate(&mut self, len: usize) {
+        self.events.truncate(len);
+    }
+
+    /// Logs an event earlier, at a position.
+    pub(crate) fn insert(&mut self, pos: usize, event: LogEvent<T>) {
+        self.events.insert(pos, event);
+    }
+}
+
+impl<T: Eq + Hash + Clone> DepMap<T> {
+    /// Makes the map log what happens to it from now on, so it can be [replayed](Self::replay).
+    ///
+    /// Targets added, expansions, cycles, completions and invalidations are logged. Resetting or
+    /// merging the map starts the log over, and restoring a snapshot rolls the log back with it.
+    pub fn with_log(mut self) -> Self {
+        self.restart_log();
+        self
+    }
+
+    /// The log of what has happened to the map, if it is being logged.
+    pub fn log(&self) -> Option<&EventLog<T>> {
+        self.log.as_ref()
+    }
+
+    /// Recreates a map from a log of what happened to it.
+    ///
+    /// The map is in the same state as the logged one, and keeps logging. Fails if the log was
+    /// made with an unknown version of the format.
+    pub fn replay(log: &EventLog<T>) -> Result<Self, UnknownVersion> {
+        let mut map = Self::resume(log.start.clone())?;
+        for (i, event) in log.events.iter().enumerate() {
+            match event {
+                LogEvent::Added(tgts) => map.extend(tgts.iter().cloned()),
+                LogEvent::Expanded { deps, failed, .. } => {
+                    // Resolve cycles as they were; they are logged after the expansion.
+                    let mut resolutions = log.events[i + 1..].iter()
+                        .take_while(|event| matches!(event,
+                            LogEvent::Cycle { .. } | LogEvent::Completed(_)))
+                        .filter_map(|event| match event {
+                            LogEvent::Cycle { resolution, .. } => Some(*resolution),
+                            _ => None,
+                        });
+                    let mut policy = CyclePolicy::callback(|_| {
+                        resolutions.next().unwrap_or(Resolution::Fail)
+                    });
+                    let deps = deps.iter()
+                        .cloned()
+                        .map(Ok)
+                        .chain(failed.then(|| Err(())));
+                    // Errors happen just as they did.
+                    let _ = map.expand_unlogged(|_| deps, &mut policy);
+                },
+                LogEvent::Cycle { .. } | LogEvent::Completed(_) => {},
+                LogEvent::Invalidated(tgt) => {
+                    map.invalidate(tgt);
+                },
+            }
+        }
+        map.log = Some(log.clone());
+        Ok(map)
+    }
+
+    /// Starts the log over from the map's current state, if it is being logged.
+    pub(crate) fn restart_log(&mut self) {
+        let start = self.checkpoint();
+        self.log = Some(EventLog {
+            start,
+            events: Vec::new(),
+        });
+    }
+}
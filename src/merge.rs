@@ -0,0 +1,73 @@
+//! Combining dependency maps.
+
+use std::fmt;
+use std::hash::Hash;
+
+use crate::DepMap;
+
+/// An error merging two dependency maps.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MergeError<T> {
+    /// A target is active in the first map, but done in the second.
+    Active(T),
+    /// The maps recorded different dependencies for a target.
+    Deps(T),
+}
+
+impl<T: fmt::Display> fmt::Display for MergeError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MergeError::Active(tgt) => {
+                write!(f, "{} is active in one map but done in the other", tgt)
+            },
+            MergeError::Deps(tgt) => write!(f, "{} has different dependencies in each map", tgt),
+        }
+    }
+}
+
+impl<T: fmt::Debug + fmt::Display> std::error::Error for MergeError<T> {}
+
+impl<T: Eq + Hash + Clone> DepMap<T> {
+    /// Merges another map into this one, e.g to combine work done separately.
+    ///
+    /// The other map's completed targets are added to the result list, after this map's, and
+    /// its pending targets (including active ones) are added as initial targets; see
+    /// [`Extend`]. Recorded dependencies are merged if this map records them. This map's options
+    /// are kept, and its [log](Self::with_log) starts over.
+    pub fn merge(mut self, other: Self) -> Result<Self, MergeError<T>> {
+        // A target active here cannot be completed before its dependencies are.
+        if let Some(tgt) = self.active.keys().find(|tgt| other.done.contains(*tgt)) {
+            return Err(MergeError::Active(tgt.clone()))
+        }
+        if let (Some(ours), Some(theirs)) = (&self.edges, &other.edges) {
+            let conflict = theirs.iter()
+                .find(|(tgt, deps)| ours.get(*tgt).is_some_and(|ours| ours != *deps));
+            if let Some((tgt, _)) = conflict {
+                return Err(MergeError::Deps(tgt.clone()))
+            }
+        }
+
+        let DepMap { list, used, result, done, edges, parents, .. } = other;
+        for tgt in result {
+            if self.done.insert(tgt.clone()) {
+                self.result.push(tgt);
+            }
+        }
+        self.done.extend(done);
+        self.generation += 1;
+        self.last_add = None;
+        if let (Some(ours), Some(theirs)) = (&mut self.edges, edges) {
+            for (tgt, deps) in theirs {
+                ours.entry(tgt).or_insert(deps);
+            }
+            for (tgt, parent) in parents {
+                self.parents.entry(tgt).or_insert(parent);
+            }
+        }
+        self.extend(list.into_iter().take(used).flatten());
+        if self.log.is_some() {
+            self.restart_log();
+        }
+        Ok(self)
+    }
+}
@@ -0,0 +1,92 @@
+//! Mermaid export.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Write};
+use std::hash::Hash;
+
+use crate::{components, DepMap};
+
+impl<T: Eq + Hash + Clone + fmt::Display> DepMap<T> {
+    /// Renders the map as a Mermaid flowchart.
+    ///
+    /// Targets are styled like [`to_dot`](Self::to_dot) does, and targets and edges in cycles are
+    /// drawn red. Edges are only drawn if the map [records them](Self::with_edges).
+    pub fn to_mermaid(&self) -> String {
+        let mut out = String::from("graph TD\n");
+        let nodes = self.node_refs();
+        let pos = nodes.iter()
+            .enumerate()
+            .map(|(i, &tgt)| (tgt, i))
+            .collect::<HashMap<_, _>>();
+        for (i, tgt) in nodes.iter().enumerate() {
+            writeln!(out, "    n{}[{}]", i, quote(tgt)).unwrap();
+        }
+
+        // Find the targets in cycles, i.e in components with more than one target or depending
+        // on themselves.
+        let deps = (0..nodes.len())
+            .map(|i| (i, self.recorded(nodes[i]).iter().map(|dep| pos[dep]).collect()))
+            .collect::<HashMap<_, Vec<_>>>();
+        let mut comp = vec![0; nodes.len()];
+        let mut cyclic = HashSet::new();
+        for (c, members) in components((0..nodes.len()).collect(), &deps).into_iter().enumerate() {
+            if members.len() > 1 || deps[&members[0]].contains(&members[0]) {
+                cyclic.insert(c);
+            }
+            for i in members {
+                comp[i] = c;
+            }
+        }
+
+        // Draw edges, noting those within cycles.
+        let mut red = Vec::new();
+        let mut link = 0;
+        for i in 0..nodes.len() {
+            for &dep in &deps[&i] {
+                writeln!(out, "    n{} --> n{}", i, dep).unwrap();
+                if comp[i] == comp[dep] && cyclic.contains(&comp[i]) {
+                    red.push(link.to_string());
+                }
+                link += 1;
+            }
+        }
+
+        // Style targets by state.
+        let active = self.active_chain().collect::<HashSet<_>>();
+        let done = self.result.iter().collect::<HashSet<_>>();
+        out.push_str("    classDef done fill:palegreen\n");
+        out.push_str("    classDef active fill:gold\n");
+        out.push_str("    classDef pending stroke-dasharray:5 5\n");
+        out.push_str("    classDef cycle stroke:red,stroke-width:2px\n");
+        for (i, tgt) in nodes.iter().enumerate() {
+            if done.contains(tgt) {
+                writeln!(out, "    class n{} done", i).unwrap();
+            } else if active.contains(tgt) {
+                writeln!(out, "    class n{} active", i).unwrap();
+            } else {
+                writeln!(out, "    class n{} pending", i).unwrap();
+            }
+            if cyclic.contains(&comp[i]) {
+                writeln!(out, "    class n{} cycle", i).unwrap();
+            }
+        }
+        if !red.is_empty() {
+            writeln!(out, "    linkStyle {} stroke:red,stroke-width:2px", red.join(",")).unwrap();
+        }
+        out
+    }
+}
+
+/// Renders a target as a quoted Mermaid label.
+fn quote<T: fmt::Display>(tgt: &T) -> String {
+    let mut out = String::from("\"");
+    for c in tgt.to_string().chars() {
+        match c {
+            '"' => out.push_str("#quot;"),
+            '#' => out.push_str("#35;"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
@@ -0,0 +1,37 @@
+//! Conversions from Cargo metadata.
+
+use cargo_metadata::{DependencyKind, Metadata, PackageId};
+
+use crate::{DepMap, EdgeKind};
+
+impl DepMap<PackageId> {
+    /// Creates a new [`DepMap`] from the resolved dependency graph of Cargo metadata, only
+    /// following dependencies of the given kinds.
+    ///
+    /// Normal dependencies are [`EdgeKind::Run`], development dependencies are [`EdgeKind::Dev`]
+    /// and build dependencies are [`EdgeKind::Build`]. Workspace members come first, then every
+    /// other package; if the metadata was read without resolving dependencies, there are no
+    /// edges. See [`from_edges`](Self::from_edges).
+    pub fn from_cargo_metadata(metadata: &Metadata, kinds: &[EdgeKind]) -> Self {
+        let nodes = metadata.workspace_members.iter()
+            .chain(metadata.packages.iter().map(|pkg| &pkg.id))
+            .cloned();
+        let edges = metadata.resolve.iter()
+            .flat_map(|resolve| &resolve.nodes)
+            .flat_map(|node| node.deps.iter()
+                .filter(|dep| {
+                    // Old versions of Cargo give no kinds; Assume normal dependencies
+                    if dep.dep_kinds.is_empty() {
+                        return kinds.contains(&EdgeKind::Run)
+                    }
+                    dep.dep_kinds.iter().any(|info| match info.kind {
+                        DependencyKind::Normal => kinds.contains(&EdgeKind::Run),
+                        DependencyKind::Development => kinds.contains(&EdgeKind::Dev),
+                        DependencyKind::Build => kinds.contains(&EdgeKind::Build),
+                        _ => false,
+                    })
+                })
+                .map(move |dep| (node.id.clone(), dep.pkg.clone())));
+        Self::from_graph(nodes, edges)
+    }
+}
@@ -0,0 +1,43 @@
+//! Serializing maps as sequences of pairs, so targets need not be strings in formats like JSON.
+//!
+//! Used with `#[serde(with = "crate::pairs")]`.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Serializes a map as a sequence of pairs.
+pub(crate) fn serialize<K, V, S>(map: &HashMap<K, V>, ser: S) -> Result<S::Ok, S::Error>
+where K: Serialize, V: Serialize, S: Serializer {
+    ser.collect_seq(map)
+}
+
+/// Deserializes a map from a sequence of pairs.
+pub(crate) fn deserialize<'de, K, V, D>(de: D) -> Result<HashMap<K, V>, D::Error>
+where K: Deserialize<'de> + Eq + Hash, V: Deserialize<'de>, D: Deserializer<'de> {
+    Ok(Vec::<(K, V)>::deserialize(de)?.into_iter().collect())
+}
+
+/// Serializing optional maps as optional sequences of pairs.
+///
+/// Used with `#[serde(with = "crate::pairs::option")]`.
+pub(crate) mod option {
+    use std::collections::HashMap;
+    use std::hash::Hash;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    /// Serializes an optional map as an optional sequence of pairs.
+    pub(crate) fn serialize<K, V, S>(map: &Option<HashMap<K, V>>, ser: S)
+        -> Result<S::Ok, S::Error>
+    where K: Serialize, V: Serialize, S: Serializer {
+        map.as_ref().map(|map| map.iter().collect::<Vec<_>>()).serialize(ser)
+    }
+
+    /// Deserializes an optional map from an optional sequence of pairs.
+    pub(crate) fn deserialize<'de, K, V, D>(de: D) -> Result<Option<HashMap<K, V>>, D::Error>
+    where K: Deserialize<'de> + Eq + Hash, V: Deserialize<'de>, D: Deserializer<'de> {
+        Ok(Option::<Vec<(K, V)>>::deserialize(de)?.map(|pairs| pairs.into_iter().collect()))
+    }
+}
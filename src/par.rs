@@ -0,0 +1,40 @@
+//! Parallel dependency discovery.
+
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+use rayon::prelude::*;
+
+use crate::{DepMap, Error};
+
+impl<T: Eq + Hash + Clone + Send + Sync> DepMap<T> {
+    /// Runs through a whole dependency map, producing dependencies on the rayon thread pool.
+    ///
+    /// Dependencies are discovered breadth-first, with every target of each round expanded in
+    /// parallel, and then ordered as [`process`](Self::process) would. The result (and which error
+    /// is returned, if any) does not depend on how the work was scheduled.
+    pub fn par_process<F, I, E>(initial: Vec<T>, f: F) -> Result<Vec<T>, Error<T, E>>
+    where F: Fn(&T) -> Result<I, E> + Sync, I: IntoIterator<Item = T>, E: Send {
+        // The dependencies of every target discovered so far.
+        let mut deps = HashMap::<T, Vec<T>>::new();
+        let mut seen = HashSet::new();
+        let mut round = initial.iter()
+            .filter(|tgt| seen.insert((*tgt).clone()))
+            .cloned()
+            .collect::<Vec<_>>();
+        while !round.is_empty() {
+            let found = round.par_iter()
+                .map(|tgt| (f)(tgt).map(|list| list.into_iter().collect::<Vec<_>>()))
+                .collect::<Vec<_>>();
+            let mut next = Vec::new();
+            for (tgt, list) in round.into_iter().zip(found) {
+                let list = list?;
+                next.extend(list.iter().filter(|dep| seen.insert((*dep).clone())).cloned());
+                deps.insert(tgt, list);
+            }
+            round = next;
+        }
+
+        Self::process(initial, |tgt| deps[tgt].iter().cloned().map(Ok))
+    }
+}
@@ -0,0 +1,50 @@
+//! Splitting results between initial targets.
+
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+use crate::graph::reachable;
+use crate::{DepMap, Error};
+
+/// How targets required by several initial targets are attributed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shared {
+    /// Attribute them to the first initial target requiring them.
+    First,
+    /// Attribute them to every initial target requiring them.
+    Duplicate,
+}
+
+impl<T: Eq + Hash + Clone> DepMap<T> {
+    /// Runs through a whole dependency map, splitting the result list between the initial
+    /// targets.
+    ///
+    /// Every initial target is paired with what it requires, ending with itself, in the order of
+    /// the result list. Initial targets are kept in order; targets they share are attributed
+    /// according to `shared`.
+    #[allow(clippy::type_complexity)]
+    pub fn process_partitioned<F, I, E>(initial: Vec<T>, shared: Shared, f: F)
+        -> Result<Vec<(T, Vec<T>)>, Error<T, E>>
+    where F: FnMut(&T) -> I, I: IntoIterator<Item = Result<T, E>> {
+        let (order, deps) = Self::process_recording(initial.clone(), f)?;
+        let pos = order.iter()
+            .enumerate()
+            .map(|(i, tgt)| (tgt, i))
+            .collect::<HashMap<_, _>>();
+        // Targets already attributed, if only attributing them once.
+        let mut claimed = HashSet::new();
+        let mut parts = Vec::with_capacity(initial.len());
+        for root in initial {
+            let mut part = reachable(&root, |tgt| deps.get(tgt).into_iter().flatten())
+                .into_iter()
+                .chain(pos.get_key_value(&root).map(|(&root, _)| root))
+                .filter(|tgt| shared == Shared::Duplicate || claimed.insert(*tgt))
+                .collect::<Vec<_>>();
+            part.sort_by_key(|tgt| pos[tgt]);
+            part.dedup();
+            let part = part.into_iter().cloned().collect();
+            parts.push((root, part));
+        }
+        Ok(parts)
+    }
+}
@@ -0,0 +1,137 @@
+//! Caching plans on disk.
+
+use std::collections::HashMap;
+use std::fs;
+use std::hash::Hash;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::hash::hash_one;
+use crate::{DepMap, Error, Plan};
+
+/// A cache of plans stored in a file, for tools that resolve the same maps repeatedly.
+///
+/// Plans are stored by their [graph hash](Plan::graph_hash), so identical plans are only stored
+/// once, and are looked up by their initial list. Changes are only written by
+/// [`save`](Self::save).
+pub struct Cache<T: Eq + Hash + Clone> {
+    /// The file the cache is stored in.
+    path: PathBuf,
+    /// The contents of the cache.
+    stored: Stored<T>,
+}
+
+/// The contents of a [`Cache`] file.
+#[derive(Serialize, Deserialize)]
+struct Stored<T: Eq + Hash + Clone> {
+    /// The version of the format.
+    version: u32,
+    /// The graph hash of the plan for every hashed initial list.
+    roots: HashMap<u64, u64>,
+    /// Every plan, by graph hash.
+    plans: HashMap<u64, Plan<T>>,
+}
+
+/// The version of a [`Cache`] file, read before the rest, whose layout depends on it.
+#[derive(Deserialize)]
+struct Version {
+    /// The version of the format.
+    version: u32,
+}
+
+impl<T: Eq + Hash + Clone + Serialize + DeserializeOwned> Cache<T> {
+    /// The version of the file format written by this version of the crate.
+    pub const VERSION: u32 = 2;
+
+    /// Loads a cache from a file.
+    ///
+    /// A missing file gives an empty cache, as does one written by another version of the
+    /// crate. Other files which cannot be read are errors.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let empty = Stored {
+            version: Self::VERSION,
+            roots: HashMap::new(),
+            plans: HashMap::new(),
+        };
+        let stored = match fs::read(&path) {
+            Ok(bytes) => match serde_json::from_slice::<Version>(&bytes)? {
+                Version { version: Self::VERSION } => serde_json::from_slice(&bytes)?,
+                _ => empty,
+            },
+            Err(err) if err.kind() == io::ErrorKind::NotFound => empty,
+            Err(err) => return Err(err),
+        };
+        Ok(Self { path, stored })
+    }
+
+    /// Writes the cache to its file.
+    pub fn save(&self) -> io::Result<()> {
+        fs::write(&self.path, serde_json::to_vec(&self.stored)?)
+    }
+
+    /// The plan for an initial list, if it is cached.
+    pub fn get(&self, roots: &[T]) -> Option<&Plan<T>> {
+        let hash = self.stored.roots.get(&hash_one(roots))?;
+        self.stored.plans.get(hash)
+    }
+
+    /// The plan for an initial list, resolving and caching it if it is not cached.
+    pub fn get_or_resolve<F, I, E>(&mut self, roots: Vec<T>, f: F)
+        -> Result<&Plan<T>, Error<T, E>>
+    where F: FnMut(&T) -> I, I: IntoIterator<Item = Result<T, E>> {
+        let key = hash_one(&roots);
+        let hash = match self.stored.roots.get(&key) {
+            Some(&hash) => hash,
+            None => self.insert(&roots, DepMap::resolve(roots.clone(), f)?),
+        };
+        Ok(&self.stored.plans[&hash])
+    }
+
+    /// Caches the plan for an initial list, returning its graph hash.
+    pub fn insert(&mut self, roots: &[T], plan: Plan<T>) -> u64 {
+        let hash = plan.graph_hash();
+        if let Some(old) = self.stored.roots.insert(hash_one(roots), hash) {
+            self.forget(old);
+        }
+        self.stored.plans.insert(hash, plan);
+        hash
+    }
+
+    /// Forgets the plan for an initial list, returning whether it was cached.
+    pub fn invalidate(&mut self, roots: &[T]) -> bool {
+        match self.stored.roots.remove(&hash_one(roots)) {
+            Some(hash) => {
+                self.forget(hash);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Forgets every plan.
+    pub fn clear(&mut self) {
+        self.stored.roots.clear();
+        self.stored.plans.clear();
+    }
+
+    /// The number of cached initial lists.
+    pub fn len(&self) -> usize {
+        self.stored.roots.len()
+    }
+
+    /// Whether nothing is cached.
+    pub fn is_empty(&self) -> bool {
+        self.stored.roots.is_empty()
+    }
+
+    /// Drops a plan if no initial list uses it any more.
+    fn forget(&mut self, hash: u64) {
+        if !self.stored.roots.values().any(|&used| used == hash) {
+            self.stored.plans.remove(&hash);
+        }
+    }
+}
@@ -0,0 +1,73 @@
+//! Executing dependency maps over channels.
+
+use std::hash::Hash;
+use std::thread::{self, JoinHandle};
+
+use crossbeam_channel::{unbounded, Receiver, Sender};
+
+use crate::{DepMap, Error, Scheduler};
+
+/// Channels to a coordinator thread handing out targets as they become ready.
+///
+/// Workers receive targets from [`ready`](Self::ready) and send them back on
+/// [`completions`](Self::completions) once done. Both ends can be cloned freely, so existing
+/// worker pools need no other glue. The `ready` channel is closed once every target is complete.
+pub struct Pipeline<T> {
+    /// The targets whose dependencies are all complete.
+    pub ready: Receiver<T>,
+    /// Where completed targets are reported.
+    pub completions: Sender<T>,
+    /// The coordinator thread, returning whether every target was completed.
+    coordinator: JoinHandle<bool>,
+}
+
+impl<T> Pipeline<T> {
+    /// Waits for the coordinator to stop, returning whether every target was completed.
+    ///
+    /// The coordinator stops early once every completion sender or ready receiver is dropped.
+    pub fn join(self) -> bool {
+        let Pipeline { ready, completions, coordinator } = self;
+        drop((ready, completions));
+        coordinator.join().unwrap_or(false)
+    }
+}
+
+impl<T: Eq + Hash + Clone + Send + 'static> Scheduler<T> {
+    /// Moves the scheduler onto a coordinator thread, working with it over channels.
+    pub fn into_pipeline(mut self) -> Pipeline<T> {
+        let (ready_tx, ready) = unbounded();
+        let (completions, done_rx) = unbounded::<T>();
+        let coordinator = thread::spawn(move || loop {
+            while let Some(tgt) = self.next_ready() {
+                if ready_tx.send(tgt).is_err() {
+                    // No workers left
+                    return false
+                }
+            }
+            if self.is_finished() {
+                return true
+            }
+            match done_rx.recv() {
+                Ok(tgt) => {
+                    self.complete(&tgt);
+                },
+                // No workers left
+                Err(_) => return false,
+            }
+        });
+        Pipeline {
+            ready,
+            completions,
+            coordinator,
+        }
+    }
+}
+
+impl<T: Eq + Hash + Clone + Send + 'static> DepMap<T> {
+    /// Runs through a whole dependency map, then hands out targets over channels as their
+    /// dependencies are completed; see [`Pipeline`].
+    pub fn pipeline<F, I, E>(initial: Vec<T>, resolver: F) -> Result<Pipeline<T>, Error<T, E>>
+    where F: FnMut(&T) -> I, I: IntoIterator<Item = Result<T, E>> {
+        Ok(Self::schedule(initial, resolver)?.into_pipeline())
+    }
+}
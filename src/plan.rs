@@ -0,0 +1,208 @@
+//! Resolving dependency maps ahead of working on them.
+
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+use crate::exec::run_scheduled;
+use crate::{DepMap, Error, Scheduler};
+
+/// A resolved dependency map: every target in dependency order, along with its dependencies.
+///
+/// Returned by [`DepMap::resolve`]. Plans are immutable, so they can be inspected, kept and
+/// executed separately from resolution.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Plan<T: Eq + Hash + Clone> {
+    /// Every target, in dependency order.
+    order: Vec<T>,
+    /// The dependencies of every target.
+    #[cfg_attr(feature = "serde", serde(with = "crate::pairs"))]
+    deps: HashMap<T, Vec<T>>,
+}
+
+/// Options for executing a [`Plan`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExecOptions {
+    /// The maximum number of targets worked on at once.
+    pub jobs: usize,
+    /// The number of times a failed target is retried before giving up.
+    pub retries: usize,
+}
+
+impl Default for ExecOptions {
+    fn default() -> Self {
+        Self {
+            jobs: 1,
+            retries: 0,
+        }
+    }
+}
+
+impl<T: Eq + Hash + Clone> Plan<T> {
+    /// Every target, in dependency order.
+    pub fn order(&self) -> &[T] {
+        &self.order
+    }
+
+    /// The dependencies of a target, in the order they were produced.
+    pub fn deps_of(&self, tgt: &T) -> &[T] {
+        self.deps.get(tgt).map_or(&[], Vec::as_slice)
+    }
+
+    /// The number of targets.
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    /// Whether there are no targets.
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    /// Creates a new [`Scheduler`] over the plan, for executing it externally.
+    pub fn scheduler(&self) -> Scheduler<T> {
+        Scheduler::new(self.order.clone(), &self.deps)
+    }
+
+    /// Compares the plan to a newer one, e.g before executing a new release.
+    pub fn diff(&self, new: &Self) -> PlanDiff<T> {
+        let old_pos = self.order.iter()
+            .enumerate()
+            .map(|(i, tgt)| (tgt, i))
+            .collect::<HashMap<_, _>>();
+        let new_pos = new.order.iter().collect::<HashSet<_>>();
+
+        // Targets in both plans, in the new order, with their old positions.
+        let common = new.order.iter()
+            .filter_map(|tgt| old_pos.get(tgt).map(|&pos| (tgt, pos)))
+            .collect::<Vec<_>>();
+        let kept = increasing(&common.iter().map(|&(_, pos)| pos).collect::<Vec<_>>());
+
+        PlanDiff {
+            added: new.order.iter().filter(|tgt| !old_pos.contains_key(tgt)).cloned().collect(),
+            removed: self.order.iter().filter(|tgt| !new_pos.contains(tgt)).cloned().collect(),
+            moved: common.iter()
+                .enumerate()
+                .filter(|&(i, _)| !kept.contains(&i))
+                .map(|(_, &(tgt, _))| tgt.clone())
+                .collect(),
+            added_edges: new.edges_missing_from(self),
+            removed_edges: self.edges_missing_from(new),
+        }
+    }
+
+    /// Every distinct edge not in another plan, in this plan's order.
+    fn edges_missing_from(&self, other: &Self) -> Vec<(T, T)> {
+        let mut seen = HashSet::new();
+        let mut out = Vec::new();
+        for tgt in &self.order {
+            for dep in self.deps_of(tgt) {
+                if !other.deps_of(tgt).contains(dep) && seen.insert((tgt, dep)) {
+                    out.push((tgt.clone(), dep.clone()));
+                }
+            }
+        }
+        out
+    }
+
+    /// Consumes the plan, returning the result list and the dependencies of every target.
+    pub fn into_parts(self) -> (Vec<T>, HashMap<T, Vec<T>>) {
+        (self.order, self.deps)
+    }
+}
+
+/// The differences between two [`Plan`]s.
+///
+/// Returned by [`Plan::diff`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PlanDiff<T> {
+    /// Targets only in the new plan, in its order.
+    pub added: Vec<T>,
+    /// Targets only in the old plan, in its order.
+    pub removed: Vec<T>,
+    /// Targets in both plans which were moved relative to the rest, in the new plan's order.
+    ///
+    /// This is the fewest targets that can be taken out of both plans to leave them in the same
+    /// order.
+    pub moved: Vec<T>,
+    /// Edges only in the new plan, from a target to a dependency.
+    pub added_edges: Vec<(T, T)>,
+    /// Edges only in the old plan, from a target to a dependency.
+    pub removed_edges: Vec<(T, T)>,
+}
+
+impl<T> PlanDiff<T> {
+    /// Whether the plans are the same.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.moved.is_empty()
+            && self.added_edges.is_empty() && self.removed_edges.is_empty()
+    }
+}
+
+impl<T: Eq + Hash + Clone + Send> Plan<T> {
+    /// Works on every target, one at a time, in dependency order.
+    ///
+    /// See [`execute_with`](Self::execute_with).
+    pub fn execute<W, R, E>(&self, worker: W) -> Result<Vec<(T, R)>, E>
+    where W: Fn(&T) -> Result<R, E> + Sync, R: Send, E: Send {
+        self.execute_with(ExecOptions::default(), worker)
+    }
+
+    /// Works on every target on a pool of threads, retrying failed targets.
+    ///
+    /// A target is only worked on once all its dependencies are complete, and the worker's
+    /// results are returned in completion order. If a target still fails after every retry, no
+    /// more targets are started, and its last error is returned once the running ones finish.
+    /// Panics in the worker are passed on.
+    pub fn execute_with<W, R, E>(&self, options: ExecOptions, worker: W)
+        -> Result<Vec<(T, R)>, E>
+    where W: Fn(&T) -> Result<R, E> + Sync, R: Send, E: Send {
+        let worker = |tgt: &T| {
+            let mut retries = options.retries;
+            loop {
+                match (worker)(tgt) {
+                    Err(_) if retries > 0 => retries -= 1,
+                    res => break res,
+                }
+            }
+        };
+        run_scheduled(self.scheduler(), worker, options.jobs, |_| Vec::<()>::new(),
+            &HashMap::new())
+    }
+}
+
+/// Finds a longest strictly increasing subsequence of a list, returning the indices in it.
+fn increasing(list: &[usize]) -> HashSet<usize> {
+    // The index of the last item of the best subsequence of every length found so far.
+    let mut tails = Vec::<usize>::new();
+    // The index of the item before every item in its subsequence.
+    let mut prev = vec![None; list.len()];
+    for (i, &item) in list.iter().enumerate() {
+        let len = tails.partition_point(|&j| list[j] < item);
+        prev[i] = len.checked_sub(1).map(|len| tails[len]);
+        if len == tails.len() {
+            tails.push(i);
+        } else {
+            tails[len] = i;
+        }
+    }
+
+    // Walk back from the end of the longest.
+    let mut out = HashSet::new();
+    let mut cur = tails.last().copied();
+    while let Some(i) = cur {
+        out.insert(i);
+        cur = prev[i];
+    }
+    out
+}
+
+impl<T: Eq + Hash + Clone> DepMap<T> {
+    /// Runs through a whole dependency map, returning a [`Plan`] to be executed separately.
+    pub fn resolve<F, I, E>(initial: Vec<T>, f: F) -> Result<Plan<T>, Error<T, E>>
+    where F: FnMut(&T) -> I, I: IntoIterator<Item = Result<T, E>> {
+        let (order, deps) = Self::process_recording(initial, f)?;
+        Ok(Plan { order, deps })
+    }
+}
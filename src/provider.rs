@@ -0,0 +1,21 @@
+//! Types that know their own dependencies.
+
+use std::convert::Infallible;
+use std::hash::Hash;
+
+use crate::{DepMap, Error};
+
+/// A type that can produce its own dependencies.
+///
+/// With the `derive` feature, this can be derived using `#[derive(Dependencies)]`.
+pub trait DependencyProvider: Sized {
+    /// Returns the dependencies of this target.
+    fn dependencies(&self) -> Vec<Self>;
+}
+
+impl<T: Eq + Hash + Clone + DependencyProvider> DepMap<T> {
+    /// Runs through a whole dependency map of targets that know their own dependencies.
+    pub fn process_auto(initial: Vec<T>) -> Result<Vec<T>, Error<T, Infallible>> {
+        Self::process(initial, |tgt| tgt.dependencies().into_iter().map(Ok))
+    }
+}
@@ -0,0 +1,87 @@
+//! Retrying failed producer functions.
+
+use std::hash::Hash;
+use std::thread;
+use std::time::Duration;
+
+use crate::{CyclePolicy, DepMap, Error};
+
+/// How failures of producer functions are retried.
+#[allow(clippy::type_complexity)]
+pub enum RetryPolicy<'a, T, E> {
+    /// Fail immediately.
+    Never,
+    /// Retry immediately, up to a number of times per target.
+    Fixed(usize),
+    /// Retry up to a number of times per target, waiting twice as long each time.
+    Backoff {
+        /// The maximum number of retries per target.
+        retries: usize,
+        /// How long to wait before the first retry.
+        initial: Duration,
+        /// The longest to wait before any retry.
+        max: Duration,
+    },
+    /// Decide with a callback, given the target, the error and the number of failures for the
+    /// target so far (starting from 1). It returns how long to wait before retrying, or `None`
+    /// to fail.
+    Callback(Box<dyn FnMut(&T, &E, usize) -> Option<Duration> + 'a>),
+}
+
+impl<'a, T, E> RetryPolicy<'a, T, E> {
+    /// Creates a [`RetryPolicy::Callback`] from a closure.
+    pub fn callback<F: FnMut(&T, &E, usize) -> Option<Duration> + 'a>(f: F) -> Self {
+        RetryPolicy::Callback(Box::new(f))
+    }
+
+    /// Decides whether to retry a failure, returning how long to wait first.
+    fn retry(&mut self, tgt: &T, err: &E, failures: usize) -> Option<Duration> {
+        match self {
+            RetryPolicy::Never => None,
+            RetryPolicy::Fixed(retries) => (failures <= *retries).then_some(Duration::ZERO),
+            RetryPolicy::Backoff { retries, initial, max } => (failures <= *retries).then(|| {
+                let factor = 1u32.checked_shl(failures as u32 - 1).unwrap_or(u32::MAX);
+                initial.saturating_mul(factor).min(*max)
+            }),
+            RetryPolicy::Callback(f) => (f)(tgt, err, failures),
+        }
+    }
+}
+
+impl<T: Eq + Hash + Clone> DepMap<T> {
+    /// Runs through a whole dependency map using a producer function, retrying it when it fails
+    /// as the given policy says.
+    ///
+    /// Dependencies produced before a failure are discarded, so the producer starts over for the
+    /// target when retried. The last error is returned if the policy gives up.
+    pub fn process_retrying<F, I, E>(initial: Vec<T>, mut policy: RetryPolicy<T, E>, mut f: F)
+        -> Result<Vec<T>, Error<T, E>>
+    where F: FnMut(&T) -> I, I: IntoIterator<Item = Result<T, E>> {
+        // The current map.
+        let mut state = Self::new(initial);
+        // The number of failures for the latest active target.
+        let mut failures = 0;
+        loop {
+            match state.destroy() {
+                Ok(res) => break Ok(res),
+                Err(map) => state = map,
+            };
+
+            // Not empty; Process
+            match state.expand(&mut f, &mut CyclePolicy::Fail) {
+                Ok(None) => failures = 0,
+                Ok(Some((pos, dep))) => break Err(Error::CyclicDep(state.take_cycle(pos, dep))),
+                Err(Error::UserDef(err)) => {
+                    failures += 1;
+                    match policy.retry(state.current().unwrap(), &err, failures) {
+                        Some(delay) => if !delay.is_zero() {
+                            thread::sleep(delay);
+                        },
+                        None => break Err(Error::UserDef(err)),
+                    }
+                },
+                Err(err) => break Err(err),
+            }
+        }
+    }
+}
@@ -0,0 +1,151 @@
+//! Snapshotting and rolling back dependency maps.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::Hash;
+
+use crate::DepMap;
+
+/// A snapshot of a dependency map's progress, which it can be rolled back to.
+///
+/// Returned by [`DepMap::snapshot`]. Only the pending lists are copied; everything else is
+/// rolled back by undoing what was added since.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Snapshot<T> {
+    /// The generation of the map.
+    generation: usize,
+    /// The length of the result list.
+    result: usize,
+    /// The number of dropped targets.
+    dropped: usize,
+    /// The used lists.
+    lists: Vec<Vec<T>>,
+    /// The number of targets in used lists.
+    pending: usize,
+    /// The recorded dependencies of the latest active target, left by a failed expansion.
+    latest: Option<Vec<T>>,
+    /// The number of events logged, if the map was being logged.
+    events: Option<usize>,
+}
+
+/// An error restoring a snapshot the map has moved on from.
+///
+/// Snapshots are invalidated when targets are taken out of the result list (e.g by
+/// [`DepMap::invalidate`] or [`DepMap::reset`]) or added from elsewhere (by [`DepMap::merge`],
+/// or [`Extend::extend`] and so [`Injector`](crate::Injector)).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StaleSnapshot;
+
+impl fmt::Display for StaleSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the snapshot is out of date")
+    }
+}
+
+impl std::error::Error for StaleSnapshot {}
+
+impl<T: Eq + Hash + Clone> DepMap<T> {
+    /// Takes a snapshot of the map's progress, e.g before exploring speculatively.
+    ///
+    /// This only copies the pending lists, so it is much cheaper than cloning the map.
+    pub fn snapshot(&self) -> Snapshot<T> {
+        Snapshot {
+            generation: self.generation,
+            result: self.result.len(),
+            dropped: self.dropped.len(),
+            lists: self.list[..self.used].to_vec(),
+            pending: self.pending,
+            latest: self.current()
+                .and_then(|tgt| self.edges.as_ref()?.get(tgt))
+                .cloned(),
+            events: self.log.as_ref().map(|log| log.len()),
+        }
+    }
+
+    /// Rolls the map back to a snapshot of it, undoing everything done since.
+    ///
+    /// The snapshot is kept, so the map can be rolled back to it again. Fails if the map has
+    /// been changed in a way that invalidates the snapshot; see [`StaleSnapshot`].
+    pub fn restore(&mut self, snapshot: &Snapshot<T>) -> Result<(), StaleSnapshot> {
+        if snapshot.generation != self.generation
+            || snapshot.result > self.result.len()
+            || snapshot.dropped > self.dropped.len() {
+            return Err(StaleSnapshot)
+        }
+
+        // Everything done since.
+        let mut expanded = self.result.drain(snapshot.result..)
+            .chain(self.dropped.drain(snapshot.dropped..))
+            .collect::<HashSet<_>>();
+        for tgt in &expanded {
+            self.done.remove(tgt);
+        }
+        if let Some(edges) = &mut self.edges {
+            // Every active target but the latest had been expanded, so their edges are kept.
+            let before = snapshot.lists.iter()
+                .rev()
+                .skip(1)
+                .map(|list| &list[0])
+                .collect::<HashSet<_>>();
+            expanded.extend(self.list[..self.used].iter().map(|list| list[0].clone()));
+            expanded.retain(|tgt| !before.contains(tgt));
+            for tgt in &expanded {
+                edges.remove(tgt);
+            }
+            self.parents.retain(|_, parent| !expanded.contains(parent));
+            // Put back what a failed expansion of the latest left.
+            if let (Some(list), Some(deps)) = (snapshot.lists.last(), &snapshot.latest) {
+                for dep in deps {
+                    self.parents.entry(dep.clone()).or_insert_with(|| list[0].clone());
+                }
+                edges.insert(list[0].clone(), deps.clone());
+            }
+        }
+
+        // Put back the used lists, keeping the free ones.
+        let free = self.list.split_off(self.used);
+        self.list = snapshot.lists.clone();
+        self.list.extend(free);
+        self.used = snapshot.lists.len();
+        self.active = snapshot.lists.iter()
+            .enumerate()
+            .map(|(i, list)| (list[0].clone(), i))
+            .collect();
+        self.pending = snapshot.pending;
+        self.last_add = None;
+        // Forget what was logged since, or start over if logging started since.
+        match (&mut self.log, snapshot.events) {
+            (Some(log), Some(len)) => log.truncate(len),
+            (Some(_), None) => self.restart_log(),
+            (None, _) => {},
+        }
+        Ok(())
+    }
+    /// Makes the map remember how to undo the latest [`add`](Self::add) call.
+    ///
+    /// Every call then takes a [snapshot](Self::snapshot) first, e.g for interactive tools.
+    pub fn with_undo(mut self) -> Self {
+        self.undo = true;
+        self
+    }
+
+    /// Undoes the latest [`add`](Self::add) or [`add_skipping`](Self::add_skipping) call,
+    /// restoring the previous active target and pending lists.
+    ///
+    /// Only the latest call can be undone, and only until the map is changed otherwise, e.g by
+    /// [`add_all`](Self::add_all) or [`restore`](Self::restore). Returns whether one was, which
+    /// needs [`with_undo`](Self::with_undo).
+    pub fn undo_add(&mut self) -> bool {
+        match self.last_add.take() {
+            Some(snapshot) => self.restore(&snapshot).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Remembers how to undo an [`add`](Self::add) call about to be made, if enabled.
+    pub(crate) fn save_undo(&mut self) {
+        if self.undo {
+            self.last_add = Some(self.snapshot());
+        }
+    }
+}
@@ -0,0 +1,112 @@
+//! Statistics about dependency graphs.
+
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+use crate::{DepMap, Error};
+
+/// Statistics about a dependency graph.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GraphStats {
+    /// The number of targets.
+    pub nodes: usize,
+    /// The number of distinct dependencies, i.e edges from targets to dependencies.
+    pub edges: usize,
+    /// The number of targets in the longest dependency chain.
+    ///
+    /// Dependencies closing cycles are not followed.
+    pub max_depth: usize,
+    /// The most dependencies of any target.
+    pub max_fan_out: usize,
+    /// The most dependents of any target.
+    pub max_fan_in: usize,
+    /// The number of targets nothing depends on.
+    pub roots: usize,
+    /// The number of targets without dependencies.
+    pub leaves: usize,
+}
+
+impl GraphStats {
+    /// Computes statistics over every target, given the dependencies of every target.
+    fn new<'a, T, N, D>(nodes: N, mut deps: D) -> Self
+    where T: Eq + Hash + 'a, N: IntoIterator<Item = &'a T>, D: FnMut(&T) -> &'a [T] {
+        let nodes = nodes.into_iter().collect::<Vec<_>>();
+        // The distinct dependencies of every target.
+        let uniq = nodes.iter()
+            .map(|&tgt| {
+                let mut seen = HashSet::new();
+                let list = (deps)(tgt).iter()
+                    .filter(|&dep| seen.insert(dep))
+                    .collect::<Vec<_>>();
+                (tgt, list)
+            })
+            .collect::<HashMap<_, _>>();
+        let deps_of = |tgt: &T| uniq.get(tgt).map_or(&[][..], Vec::as_slice);
+
+        let mut stats = Self {
+            nodes: nodes.len(),
+            ..Self::default()
+        };
+        let mut fan_in = HashMap::<&T, usize>::new();
+        for &tgt in &nodes {
+            let deps = deps_of(tgt);
+            stats.edges += deps.len();
+            stats.max_fan_out = stats.max_fan_out.max(deps.len());
+            if deps.is_empty() {
+                stats.leaves += 1;
+            }
+            for &dep in deps {
+                *fan_in.entry(dep).or_default() += 1;
+            }
+        }
+        stats.max_fan_in = fan_in.values().max().copied().unwrap_or(0);
+        stats.roots = nodes.iter().filter(|tgt| !fan_in.contains_key(*tgt)).count();
+
+        // The number of targets in the longest chain starting with every target.
+        let mut depth = HashMap::<&T, usize>::with_capacity(nodes.len());
+        let mut active = HashSet::new();
+        for &root in &nodes {
+            if depth.contains_key(root) {
+                continue;
+            }
+            // Walk dependencies depth-first, each target with the index of its next dependency.
+            let mut stack = vec![(root, 0)];
+            active.insert(root);
+            while let Some(&mut (tgt, ref mut i)) = stack.last_mut() {
+                if let Some(&dep) = deps_of(tgt).get(*i) {
+                    *i += 1;
+                    if !depth.contains_key(dep) && active.insert(dep) {
+                        stack.push((dep, 0));
+                    }
+                    continue;
+                }
+                // Every dependency is walked; Find the target's depth
+                let longest = deps_of(tgt).iter().filter_map(|dep| depth.get(dep)).max();
+                let d = 1 + longest.unwrap_or(&0);
+                stats.max_depth = stats.max_depth.max(d);
+                depth.insert(tgt, d);
+                active.remove(tgt);
+                stack.pop();
+            }
+        }
+        stats
+    }
+}
+
+impl<T: Eq + Hash + Clone> DepMap<T> {
+    /// Walks a whole dependency map without keeping its result, checking for errors and
+    /// gathering statistics.
+    ///
+    /// This is a pre-flight check: it fails exactly when [`process`](Self::process) would.
+    pub fn validate<F, I, E>(initial: Vec<T>, f: F) -> Result<GraphStats, Error<T, E>>
+    where F: FnMut(&T) -> I, I: IntoIterator<Item = Result<T, E>> {
+        let (order, deps) = Self::process_recording(initial, f)?;
+        Ok(GraphStats::new(&order, |tgt| deps.get(tgt).map_or(&[], Vec::as_slice)))
+    }
+
+    /// Gathers statistics about every known target using the recorded edges.
+    pub fn stats(&self) -> GraphStats {
+        GraphStats::new(self.node_refs(), |tgt| self.recorded(tgt))
+    }
+}
@@ -0,0 +1,81 @@
+//! Stepping through dependency maps.
+
+use std::collections::HashSet;
+use std::hash::Hash;
+
+use crate::{Cycle, CyclePolicy, DepMap, Error};
+
+/// What a single [`step`](DepMap::step) did to a dependency map.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StepOutcome<T> {
+    /// The target that was expanded.
+    pub target: T,
+    /// The dependencies not seen before, now pending.
+    pub new: Vec<T>,
+    /// The dependencies already pending (or produced earlier in the step), pending again.
+    pub duplicate: Vec<T>,
+    /// The dependencies already done, which were skipped.
+    pub done: Vec<T>,
+    /// The cyclic dependency found, if any.
+    ///
+    /// The target is then kept active without its dependencies, as [`add`](DepMap::add) does.
+    pub cycle: Option<Cycle<T>>,
+    /// The targets completed by the step, in order.
+    ///
+    /// This is the target itself (if it had nothing left to depend on) along with any active
+    /// targets completed after it.
+    pub completed: Vec<T>,
+}
+
+impl<T: Eq + Hash + Clone> DepMap<T> {
+    /// Expands the latest active target, describing exactly what changed, e.g for interactive
+    /// debuggers or assertions on intermediate states.
+    ///
+    /// This works like [`add`](Self::add), but returns `None` if the map is empty.
+    pub fn step<F, I, E>(&mut self, f: F) -> Result<Option<StepOutcome<T>>, Error<T, E>>
+    where F: FnOnce(&T) -> I, I: IntoIterator<Item = Result<T, E>> {
+        let target = match self.current() {
+            Some(tgt) => tgt.clone(),
+            None => return Ok(None),
+        };
+        let active = self.active_chain().cloned().collect::<HashSet<_>>();
+        let mut pending = self.list[..self.used].iter()
+            .flat_map(|list| &list[1..])
+            .cloned()
+            .collect::<HashSet<_>>();
+        let before = self.result.len();
+
+        self.save_undo();
+        let mut deps = Vec::new();
+        let cycle = self.expand(|tgt| (f)(tgt).into_iter().inspect(|dep| {
+            if let Ok(dep) = dep {
+                deps.push(dep.clone());
+            }
+        }), &mut CyclePolicy::Fail)?;
+
+        let mut outcome = StepOutcome {
+            target,
+            new: Vec::new(),
+            duplicate: Vec::new(),
+            done: Vec::new(),
+            cycle: cycle.map(|(pos, closed_by)| Cycle {
+                chain: self.list[pos..self.used].iter().map(|list| list[0].clone()).collect(),
+                closed_by,
+            }),
+            completed: self.result[before..].to_vec(),
+        };
+        for dep in deps {
+            if active.contains(&dep) {
+                // Closed the cycle; the last dependency produced
+                continue;
+            } else if self.done.contains(&dep) {
+                outcome.done.push(dep);
+            } else if pending.insert(dep.clone()) {
+                outcome.new.push(dep);
+            } else {
+                outcome.duplicate.push(dep);
+            }
+        }
+        Ok(Some(outcome))
+    }
+}
@@ -0,0 +1,165 @@
+//! Executing dependency maps on the `tokio` runtime.
+
+use std::future::Future;
+use std::hash::Hash;
+use std::panic;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use crate::{DepMap, Error};
+
+/// What to do when a task fails.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OnFailure {
+    /// Start no more tasks, and return the error once the running ones finish.
+    #[default]
+    Drain,
+    /// Abort the running tasks, and return the error immediately.
+    Abort,
+    /// Keep working on everything not depending on the failed target, and return the first
+    /// error at the end.
+    Continue,
+}
+
+/// Limits on the tasks spawned by [`DepMap::run_tokio`].
+#[derive(Clone, Debug)]
+pub struct TaskLimits {
+    /// The semaphore every task holds a permit of while running.
+    semaphore: Arc<Semaphore>,
+    /// What to do when a task fails.
+    on_failure: OnFailure,
+    /// How long a task may run before failing, if limited.
+    timeout: Option<Duration>,
+}
+
+impl TaskLimits {
+    /// Creates new [`TaskLimits`], running at most `jobs` tasks at once.
+    pub fn new(jobs: usize) -> Self {
+        Self::with_semaphore(Arc::new(Semaphore::new(jobs.max(1))))
+    }
+
+    /// Creates new [`TaskLimits`] sharing a semaphore, e.g to limit several runs together.
+    pub fn with_semaphore(semaphore: Arc<Semaphore>) -> Self {
+        Self {
+            semaphore,
+            on_failure: OnFailure::default(),
+            timeout: None,
+        }
+    }
+
+    /// Sets what to do when a task fails.
+    pub fn on_failure(mut self, on_failure: OnFailure) -> Self {
+        self.on_failure = on_failure;
+        self
+    }
+
+    /// Fails tasks running longer than a timeout with [`Error::Timeout`].
+    ///
+    /// Tasks that time out are dropped, and are handled like other failures.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+impl Default for TaskLimits {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+impl<T: Eq + Hash + Clone + Send + 'static> DepMap<T> {
+    /// Runs through a whole dependency map, then spawns a `tokio` task for every target once all
+    /// its dependencies are complete.
+    ///
+    /// Every task holds a permit of the limits' semaphore while running. The worker's results
+    /// are returned in completion order, and failures are handled as the limits say; see
+    /// [`OnFailure`]. Panics in tasks are resumed.
+    ///
+    /// This must be called within a `tokio` runtime.
+    pub async fn run_tokio<F, I, W, Fut, R, E>(
+        initial: Vec<T>,
+        resolver: F,
+        mut worker: W,
+        limits: TaskLimits,
+    ) -> Result<Vec<(T, R)>, Error<T, E>>
+    where
+        F: FnMut(&T) -> I,
+        I: IntoIterator<Item = Result<T, E>>,
+        W: FnMut(&T) -> Fut,
+        Fut: Future<Output = Result<R, E>> + Send + 'static,
+        R: Send + 'static,
+        E: Send + 'static,
+    {
+        let mut sched = Self::schedule(initial, resolver)?;
+        let mut tasks = JoinSet::new();
+        // The results, in completion order.
+        let mut results = Vec::new();
+        // The first error, if any.
+        let mut error = None;
+        // A target handed out but waiting for a permit.
+        let mut next = None;
+        loop {
+            // Start as many targets as possible.
+            while error.is_none() || limits.on_failure == OnFailure::Continue {
+                let tgt = match next.take().or_else(|| sched.next_ready()) {
+                    Some(tgt) => tgt,
+                    None => break,
+                };
+                let permit = if tasks.is_empty() {
+                    // Nothing else to wait for
+                    limits.semaphore.clone().acquire_owned().await
+                        .expect("the semaphore was closed")
+                } else {
+                    match limits.semaphore.clone().try_acquire_owned() {
+                        Ok(permit) => permit,
+                        Err(_) => {
+                            next = Some(tgt);
+                            break
+                        },
+                    }
+                };
+                let fut = (worker)(&tgt);
+                let timeout = limits.timeout;
+                tasks.spawn(async move {
+                    let res = match timeout {
+                        Some(timeout) => match tokio::time::timeout(timeout, fut).await {
+                            Ok(res) => res.map_err(Error::UserDef),
+                            Err(_) => Err(Error::Timeout(tgt.clone())),
+                        },
+                        None => fut.await.map_err(Error::UserDef),
+                    };
+                    drop(permit);
+                    (tgt, res)
+                });
+            }
+
+            // Wait for one to finish.
+            match tasks.join_next().await {
+                None => break,
+                Some(Ok((tgt, Ok(res)))) => {
+                    sched.complete(&tgt);
+                    results.push((tgt, res));
+                },
+                Some(Ok((_, Err(err)))) => {
+                    // Its dependents are never readied.
+                    if limits.on_failure == OnFailure::Abort {
+                        tasks.abort_all();
+                        return Err(err)
+                    }
+                    error.get_or_insert(err);
+                },
+                Some(Err(err)) => if err.is_panic() {
+                    panic::resume_unwind(err.into_panic())
+                },
+            }
+        }
+        match error {
+            Some(err) => Err(err),
+            None => Ok(results),
+        }
+    }
+}
@@ -0,0 +1,164 @@
+//! Generated graphs for testing producer functions.
+
+use std::convert::Infallible;
+
+use crate::DepMap;
+
+/// A generated dependency graph over numbered targets.
+///
+/// Targets are numbered from zero. Generators are seeded, so the same seed always gives the same
+/// graph.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TestGraph {
+    /// The dependencies of every target.
+    deps: Vec<Vec<u32>>,
+}
+
+impl TestGraph {
+    /// A random acyclic graph, each target depending on each earlier one with the given
+    /// probability.
+    pub fn random_dag(nodes: usize, density: f64, seed: u64) -> Self {
+        let mut rng = Rng(seed);
+        Self {
+            deps: (0..nodes as u32)
+                .map(|i| (0..i).filter(|_| rng.chance(density)).collect())
+                .collect(),
+        }
+    }
+
+    /// A chain, each target depending on the one before it.
+    pub fn chain(len: usize) -> Self {
+        Self {
+            deps: (0..len as u32)
+                .map(|i| if i == 0 {Vec::new()} else {vec![i - 1]})
+                .collect(),
+        }
+    }
+
+    /// A diamond: target 0 depends on `width` targets, which all depend on the last target.
+    pub fn diamond(width: usize) -> Self {
+        let bottom = width as u32 + 1;
+        let mut deps = vec![(1..bottom).collect::<Vec<_>>()];
+        deps.extend((1..bottom).map(|_| vec![bottom]));
+        deps.push(Vec::new());
+        Self { deps }
+    }
+
+    /// Plants a cycle through `len` random distinct targets, returning them in cycle order.
+    ///
+    /// Each target in the cycle depends on the next, and the last on the first. At most every
+    /// target is in the cycle.
+    pub fn plant_cycle(&mut self, len: usize, seed: u64) -> Vec<u32> {
+        let mut rng = Rng(seed);
+        let mut nodes = (0..self.deps.len() as u32).collect::<Vec<_>>();
+        // Shuffle the start of the list.
+        let len = len.min(nodes.len());
+        for i in 0..len {
+            let j = i + rng.below(nodes.len() - i);
+            nodes.swap(i, j);
+        }
+        nodes.truncate(len);
+        for (i, &tgt) in nodes.iter().enumerate() {
+            self.deps[tgt as usize].push(nodes[(i + 1) % len]);
+        }
+        nodes
+    }
+
+    /// The number of targets.
+    pub fn len(&self) -> usize {
+        self.deps.len()
+    }
+
+    /// Whether there are no targets.
+    pub fn is_empty(&self) -> bool {
+        self.deps.is_empty()
+    }
+
+    /// Every target.
+    pub fn nodes(&self) -> Vec<u32> {
+        (0..self.deps.len() as u32).collect()
+    }
+
+    /// Every edge, from a target to a dependency.
+    pub fn edges(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        self.deps.iter()
+            .enumerate()
+            .flat_map(|(i, deps)| deps.iter().map(move |&dep| (i as u32, dep)))
+    }
+
+    /// A producer function over the graph.
+    ///
+    /// Unknown targets have no dependencies.
+    pub fn deps(&self, tgt: &u32) -> impl Iterator<Item = Result<u32, Infallible>> + '_ {
+        self.deps.get(*tgt as usize).into_iter().flatten().copied().map(Ok)
+    }
+
+    /// Creates a new [`DepMap`] with every target pending and every edge recorded.
+    pub fn to_map(&self) -> DepMap<u32> {
+        DepMap::from_graph(self.nodes(), self.edges())
+    }
+
+    /// Only keeps the first `len` targets, and dependencies between them.
+    #[cfg(feature = "quickcheck")]
+    fn truncated(&self, len: usize) -> Self {
+        Self {
+            deps: self.deps[..len].iter()
+                .map(|deps| deps.iter().copied().filter(|&dep| (dep as usize) < len).collect())
+                .collect(),
+        }
+    }
+}
+
+/// A small seeded random number generator (SplitMix64).
+struct Rng(u64);
+
+impl Rng {
+    /// The next random number.
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A random number below `n`.
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    /// Whether an event with the given probability happens.
+    fn chance(&mut self, p: f64) -> bool {
+        ((self.next() >> 11) as f64 / (1u64 << 53) as f64) < p
+    }
+}
+
+#[cfg(feature = "quickcheck")]
+impl quickcheck::Arbitrary for TestGraph {
+    /// A random acyclic graph, with up to the generator's size of targets.
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        let nodes = usize::arbitrary(g) % (g.size() + 1);
+        let density = f64::from(u8::arbitrary(g)) / 255.0;
+        Self::random_dag(nodes, density, u64::arbitrary(g))
+    }
+
+    /// Smaller graphs, with later targets removed.
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        let graph = self.clone();
+        Box::new((0..graph.len()).rev().map(move |len| graph.truncated(len)))
+    }
+}
+
+#[cfg(feature = "proptest")]
+impl proptest::arbitrary::Arbitrary for TestGraph {
+    type Parameters = ();
+    type Strategy = proptest::strategy::BoxedStrategy<Self>;
+
+    /// Random acyclic graphs of up to 64 targets.
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        use proptest::prelude::*;
+        (0..64usize, 0.0..1.0f64, any::<u64>())
+            .prop_map(|(nodes, density, seed)| Self::random_dag(nodes, density, seed))
+            .boxed()
+    }
+}
@@ -0,0 +1,106 @@
+//! Timing out producer functions.
+
+#[cfg(feature = "tokio")]
+use std::future::Future;
+use std::hash::Hash;
+use std::panic;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+#[cfg(feature = "tokio")]
+use crate::CyclePolicy;
+use crate::{DepMap, Error};
+
+/// Why the dependencies of a target could not be produced.
+enum Failed<T, E> {
+    /// The producer failed.
+    User(E),
+    /// The producer took too long for the target.
+    Timeout(T),
+    /// The producer panicked.
+    Panicked,
+}
+
+impl<T: Eq + Hash + Clone + Send + 'static> DepMap<T> {
+    /// Runs through a whole dependency map using a producer function, failing if it takes
+    /// longer than a timeout for any target.
+    ///
+    /// The producer is called on a watchdog thread, so a hung call is abandoned rather than
+    /// stalling the whole map; the thread stops once the call returns. Panics in the producer are
+    /// resumed.
+    pub fn process_timeout<F, I, E>(initial: Vec<T>, timeout: Duration, mut f: F)
+        -> Result<Vec<T>, Error<T, E>>
+    where
+        F: FnMut(&T) -> I + Send + 'static,
+        I: IntoIterator<Item = Result<T, E>>,
+        E: Send + 'static,
+    {
+        let (req_tx, req_rx) = mpsc::channel::<T>();
+        let (res_tx, res_rx) = mpsc::channel();
+        let watchdog = thread::spawn(move || {
+            for tgt in req_rx {
+                let deps = (f)(&tgt).into_iter().collect::<Vec<_>>();
+                if res_tx.send(deps).is_err() {
+                    break
+                }
+            }
+        });
+
+        let res = Self::process(initial, |tgt| {
+            let res = req_tx.send(tgt.clone())
+                .map_err(|_| RecvTimeoutError::Disconnected)
+                .and_then(|()| res_rx.recv_timeout(timeout));
+            let deps = match res {
+                Ok(deps) => deps.into_iter().map(|dep| dep.map_err(Failed::User)).collect(),
+                Err(RecvTimeoutError::Timeout) => vec![Err(Failed::Timeout(tgt.clone()))],
+                Err(RecvTimeoutError::Disconnected) => vec![Err(Failed::Panicked)],
+            };
+            deps.into_iter()
+        });
+        drop(req_tx);
+        match res {
+            Ok(res) => Ok(res),
+            Err(Error::UserDef(Failed::User(err))) => Err(Error::UserDef(err)),
+            Err(Error::UserDef(Failed::Timeout(tgt))) => Err(Error::Timeout(tgt)),
+            Err(Error::UserDef(Failed::Panicked)) => match watchdog.join() {
+                Err(payload) => panic::resume_unwind(payload),
+                Ok(()) => unreachable!("the watchdog stopped early"),
+            },
+            Err(Error::CyclicDep(cycle)) => Err(Error::CyclicDep(cycle)),
+            Err(Error::LimitExceeded(limit)) => Err(Error::LimitExceeded(limit)),
+            Err(Error::Timeout(tgt)) => Err(Error::Timeout(tgt)),
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<T: Eq + Hash + Clone> DepMap<T> {
+    /// Runs through a whole dependency map using an asynchronous producer function, failing if
+    /// it takes longer than a timeout for any target.
+    ///
+    /// See [`process_async`](Self::process_async). This must be called within a `tokio` runtime.
+    pub async fn process_async_timeout<F, Fut, I, E>(initial: Vec<T>, timeout: Duration, mut f: F)
+        -> Result<Vec<T>, Error<T, E>>
+    where F: FnMut(&T) -> Fut, Fut: Future<Output = Result<I, E>>, I: IntoIterator<Item = T> {
+        // The current map.
+        let mut state = Self::new(initial);
+        loop {
+            match state.destroy() {
+                Ok(res) => break Ok(res),
+                Err(map) => state = map,
+            };
+
+            // Not empty; Fetch the dependencies, then process
+            let tgt = state.current().unwrap();
+            let deps = match tokio::time::timeout(timeout, (f)(tgt)).await {
+                Ok(deps) => deps?,
+                Err(_) => break Err(Error::Timeout(tgt.clone())),
+            };
+            let res = state.expand(|_| deps.into_iter().map(Ok::<T, E>), &mut CyclePolicy::Fail)?;
+            if let Some((pos, dep)) = res {
+                break Err(Error::CyclicDep(state.take_cycle(pos, dep)))
+            }
+        }
+    }
+}
@@ -0,0 +1,221 @@
+//! Timing analysis over recorded edges.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::convert::Infallible;
+use std::fmt::{self, Write};
+use std::hash::Hash;
+use std::time::Duration;
+
+use crate::{DepMap, Error};
+
+/// The longest chain of work through a dependency map, with the slack of every target.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CriticalPath<T: Eq + Hash> {
+    /// The chain of targets bounding the total time, dependencies first.
+    pub chain: Vec<T>,
+    /// The total cost of the chain, i.e the least time everything can be done in.
+    pub total: Duration,
+    /// How long every target could be delayed without delaying the total.
+    ///
+    /// Targets on the chain have no slack.
+    pub slack: HashMap<T, Duration>,
+}
+
+/// A target placed on a worker at some time.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Slot<T> {
+    /// The target.
+    pub target: T,
+    /// The index of the worker.
+    pub worker: usize,
+    /// When the target is started.
+    pub start: Duration,
+    /// When the target is finished.
+    pub end: Duration,
+}
+
+/// A timeline of every target on a number of workers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Schedule<T> {
+    /// Every target, by start time.
+    pub slots: Vec<Slot<T>>,
+    /// When everything is finished.
+    pub makespan: Duration,
+}
+
+impl<T: fmt::Display> Schedule<T> {
+    /// Renders the schedule as a JSON timeline, in the Chrome trace event format.
+    ///
+    /// Every slot is a complete event, named after its target, with its worker as the thread and
+    /// times in microseconds. This can be loaded into `chrome://tracing` or Perfetto, or read by
+    /// other timeline renderers.
+    pub fn to_trace_json(&self) -> String {
+        let mut out = String::from("[\n");
+        for (i, slot) in self.slots.iter().enumerate() {
+            write!(out, "  {{\"name\": {}, \"ph\": \"X\", ", json_string(&slot.target)).unwrap();
+            write!(out, "\"ts\": {}, \"dur\": {}, ", slot.start.as_micros(),
+                (slot.end - slot.start).as_micros()).unwrap();
+            write!(out, "\"pid\": 0, \"tid\": {}}}", slot.worker).unwrap();
+            out.push_str(if i + 1 < self.slots.len() {",\n"} else {"\n"});
+        }
+        out.push_str("]\n");
+        out
+    }
+}
+
+impl<T: Eq + Hash + Clone> DepMap<T> {
+    /// Finds the critical path through every known target using the recorded edges, given the
+    /// cost of every target.
+    ///
+    /// Fails if the recorded edges are cyclic.
+    pub fn critical_path<C>(&self, mut cost: C) -> Result<CriticalPath<T>, Error<T, Infallible>>
+    where C: FnMut(&T) -> Duration {
+        let order = self.toposort()?;
+        let cost = order.iter()
+            .map(|tgt| (tgt, (cost)(tgt)))
+            .collect::<HashMap<_, _>>();
+
+        // The earliest every target can finish, dependencies first.
+        let mut earliest = HashMap::<&T, Duration>::with_capacity(order.len());
+        for tgt in &order {
+            let start = self.recorded(tgt).iter()
+                .filter_map(|dep| earliest.get(dep))
+                .max()
+                .copied()
+                .unwrap_or_default();
+            earliest.insert(tgt, start + cost[tgt]);
+        }
+        let total = earliest.values().max().copied().unwrap_or_default();
+
+        // The latest every target can finish, dependents first.
+        let dependents = self.dependents();
+        let mut latest = HashMap::<&T, Duration>::with_capacity(order.len());
+        for tgt in order.iter().rev() {
+            let finish = dependents.get(tgt)
+                .into_iter()
+                .flatten()
+                .filter_map(|&dep| latest.get(dep).map(|end| end.saturating_sub(cost[dep])))
+                .min()
+                .unwrap_or(total);
+            latest.insert(tgt, finish);
+        }
+
+        // Walk back from the last target to finish along dependencies finishing just in time.
+        let mut chain = Vec::new();
+        let mut cur = order.iter().rev().max_by_key(|tgt| earliest[tgt]);
+        while let Some(tgt) = cur {
+            chain.push(tgt.clone());
+            let start = earliest[tgt] - cost[tgt];
+            cur = self.recorded(tgt).iter().find(|dep| earliest.get(dep) == Some(&start));
+        }
+        chain.reverse();
+
+        Ok(CriticalPath {
+            chain,
+            total,
+            slack: order.iter()
+                .map(|tgt| (tgt.clone(), latest[tgt].saturating_sub(earliest[tgt])))
+                .collect(),
+        })
+    }
+
+    /// Places every known target on a number of workers using the recorded edges, given the cost
+    /// of every target.
+    ///
+    /// Targets are started as soon as their dependencies are finished and a worker is idle.
+    /// Whenever several can be started, those with the longest chain of work after them go first.
+    ///
+    /// Fails if the recorded edges are cyclic.
+    pub fn timeline<C>(&self, workers: usize, mut cost: C)
+        -> Result<Schedule<T>, Error<T, Infallible>>
+    where C: FnMut(&T) -> Duration {
+        let order = self.toposort()?;
+        let cost = order.iter().map(&mut cost).collect::<Vec<_>>();
+
+        // The number of unfinished dependencies and the dependents of every target, by position.
+        let pos = order.iter()
+            .enumerate()
+            .map(|(i, tgt)| (tgt, i))
+            .collect::<HashMap<_, _>>();
+        let mut waiting = vec![0; order.len()];
+        let mut dependents = vec![Vec::new(); order.len()];
+        for (i, tgt) in order.iter().enumerate() {
+            let uniq = self.recorded(tgt).iter()
+                .map(|dep| pos[dep])
+                .collect::<HashSet<_>>();
+            waiting[i] = uniq.len();
+            for dep in uniq {
+                dependents[dep].push(i);
+            }
+        }
+
+        // The cost of the longest chain of work starting with every target, by position.
+        let mut rank = vec![Duration::ZERO; order.len()];
+        for i in (0..order.len()).rev() {
+            let after = dependents[i].iter().map(|&dep| rank[dep]).max();
+            rank[i] = cost[i] + after.unwrap_or_default();
+        }
+
+        // Start whatever can be, then wait for the next target to finish, until none are left.
+        let mut ready = (0..order.len())
+            .filter(|&i| waiting[i] == 0)
+            .map(|i| (rank[i], Reverse(i)))
+            .collect::<BinaryHeap<_>>();
+        let mut idle = (0..workers.max(1)).map(Reverse).collect::<BinaryHeap<_>>();
+        let mut running = BinaryHeap::new();
+        let mut slots = Vec::with_capacity(order.len());
+        let mut now = Duration::ZERO;
+        loop {
+            while !idle.is_empty() && !ready.is_empty() {
+                let (_, Reverse(i)) = ready.pop().unwrap();
+                let Reverse(worker) = idle.pop().unwrap();
+                running.push(Reverse((now + cost[i], worker, i)));
+                slots.push((i, worker, now));
+            }
+            let Reverse((end, worker, i)) = match running.pop() {
+                Some(next) => next,
+                None => break,
+            };
+            now = end;
+            idle.push(Reverse(worker));
+            for &dep in &dependents[i] {
+                waiting[dep] -= 1;
+                if waiting[dep] == 0 {
+                    ready.push((rank[dep], Reverse(dep)));
+                }
+            }
+        }
+
+        let mut order = order.into_iter().map(Some).collect::<Vec<_>>();
+        Ok(Schedule {
+            slots: slots.into_iter()
+                .map(|(i, worker, start)| Slot {
+                    target: order[i].take().unwrap(),
+                    worker,
+                    start,
+                    end: start + cost[i],
+                })
+                .collect(),
+            makespan: now,
+        })
+    }
+}
+
+/// Renders a target as a JSON string.
+fn json_string<T: fmt::Display>(tgt: &T) -> String {
+    let mut out = String::from("\"");
+    for c in tgt.to_string().chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
@@ -0,0 +1,36 @@
+//! Tracing instrumentation.
+
+use std::fmt;
+use std::hash::Hash;
+
+use tracing::{debug, debug_span, trace, warn};
+
+use crate::{DepMap, Error, Event};
+
+impl<T: Eq + Hash + Clone + fmt::Debug> DepMap<T> {
+    /// Runs through a whole dependency map, emitting `tracing` spans and events.
+    ///
+    /// Each call to the producer function is wrapped in an `expand` span, along with producing
+    /// the dependencies it returns (up to the first error), so lazy producers are traced too.
+    /// Completed targets and cyclic dependencies are reported as events.
+    pub fn process_traced<F, I, E>(initial: Vec<T>, mut f: F) -> Result<Vec<T>, Error<T, E>>
+    where F: FnMut(&T) -> I, I: IntoIterator<Item = Result<T, E>> {
+        let observer = |event: Event<'_, T>| match event {
+            Event::Expanding(tgt) => trace!(target = ?tgt, "expanding"),
+            Event::Completed(tgt, index) => debug!(target = ?tgt, index, "completed"),
+            Event::CycleDetected(chain) => warn!(?chain, "cyclic dependency"),
+        };
+        Self::process_observed(initial, observer, |tgt| {
+            let _span = debug_span!("expand", target = ?tgt).entered();
+            let mut deps = Vec::new();
+            for dep in (f)(tgt) {
+                let failed = dep.is_err();
+                deps.push(dep);
+                if failed {
+                    break
+                }
+            }
+            deps
+        })
+    }
+}
@@ -0,0 +1,124 @@
+//! Checking dependency maps for internal consistency.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::Hash;
+
+use crate::DepMap;
+
+/// A broken internal invariant of a dependency map, as found by [`DepMap::debug_validate`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Violation<T> {
+    /// More lists are marked used than exist.
+    UsedOutOfRange {
+        /// The number of used lists.
+        used: usize,
+        /// The number of lists.
+        lists: usize,
+    },
+    /// A used list is empty, so it has no active target.
+    EmptyList(usize),
+    /// A free list is not empty.
+    FreeList(usize),
+    /// The first target of a used list is not marked active for it.
+    Inactive(T),
+    /// A target is marked active, but is not the first of its list.
+    StrayActive(T),
+    /// An active target is already done.
+    ActiveDone(T),
+    /// A target is in the result list more than once.
+    Duplicate(T),
+    /// A target in the result list or dropped is not marked done.
+    NotDone(T),
+    /// The number of pending targets is miscounted.
+    Pending {
+        /// The number recorded.
+        recorded: usize,
+        /// The number in used lists.
+        actual: usize,
+    },
+}
+
+impl<T: fmt::Display> fmt::Display for Violation<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::UsedOutOfRange { used, lists }
+                => write!(f, "{} lists are used, but only {} exist", used, lists),
+            Violation::EmptyList(i) => write!(f, "used list {} is empty", i),
+            Violation::FreeList(i) => write!(f, "free list {} is not empty", i),
+            Violation::Inactive(tgt) => write!(f, "{} heads a used list but is not active", tgt),
+            Violation::StrayActive(tgt)
+                => write!(f, "{} is active but does not head its list", tgt),
+            Violation::ActiveDone(tgt) => write!(f, "{} is active but already done", tgt),
+            Violation::Duplicate(tgt) => write!(f, "{} is in the result list more than once", tgt),
+            Violation::NotDone(tgt) => write!(f, "{} was completed but is not marked done", tgt),
+            Violation::Pending { recorded, actual }
+                => write!(f, "{} targets are recorded pending, but {} are", recorded, actual),
+        }
+    }
+}
+
+impl<T: fmt::Debug + fmt::Display> std::error::Error for Violation<T> {}
+
+impl<T: Eq + Hash + Clone> DepMap<T> {
+    /// Checks the map's internal invariants, returning every one that is broken.
+    ///
+    /// These always hold for maps only changed through this crate, so this is for diagnosing
+    /// weird states, e.g after deserializing a map from elsewhere.
+    pub fn debug_validate(&self) -> Result<(), Vec<Violation<T>>> {
+        let mut found = Vec::new();
+        if self.used > self.list.len() {
+            found.push(Violation::UsedOutOfRange {
+                used: self.used,
+                lists: self.list.len(),
+            });
+        }
+        let used = self.used.min(self.list.len());
+
+        for (i, list) in self.list[..used].iter().enumerate() {
+            match list.first() {
+                None => found.push(Violation::EmptyList(i)),
+                Some(tgt) => {
+                    if self.active.get(tgt) != Some(&i) {
+                        found.push(Violation::Inactive(tgt.clone()));
+                    }
+                    if self.done.contains(tgt) {
+                        found.push(Violation::ActiveDone(tgt.clone()));
+                    }
+                },
+            }
+        }
+        for (i, list) in self.list.iter().enumerate().skip(used) {
+            if !list.is_empty() {
+                found.push(Violation::FreeList(i));
+            }
+        }
+        for (tgt, &i) in &self.active {
+            if i >= used || self.list[i].first() != Some(tgt) {
+                found.push(Violation::StrayActive(tgt.clone()));
+            }
+        }
+
+        let mut seen = HashSet::new();
+        for tgt in &self.result {
+            if !seen.insert(tgt) {
+                found.push(Violation::Duplicate(tgt.clone()));
+            }
+        }
+        for tgt in self.result.iter().chain(&self.dropped) {
+            if !self.done.contains(tgt) {
+                found.push(Violation::NotDone(tgt.clone()));
+            }
+        }
+
+        let actual = self.list[..used].iter().map(Vec::len).sum();
+        if self.pending != actual {
+            found.push(Violation::Pending {
+                recorded: self.pending,
+                actual,
+            });
+        }
+
+        if found.is_empty() {Ok(())} else {Err(found)}
+    }
+}
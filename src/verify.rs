@@ -0,0 +1,84 @@
+//! Checking orders against producer functions.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+
+/// A way an order fails to respect dependencies.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VerifyError<T, E> {
+    /// A target is listed more than once.
+    Duplicate(T),
+    /// A dependency of a target is not listed.
+    Missing {
+        /// The target.
+        target: T,
+        /// The missing dependency.
+        dependency: T,
+    },
+    /// A dependency of a target is listed after it.
+    OutOfOrder {
+        /// The target.
+        target: T,
+        /// The dependency listed after it.
+        dependency: T,
+    },
+    /// A user-defined error.
+    UserDef(E),
+}
+
+impl<T: fmt::Display, E: fmt::Display> fmt::Display for VerifyError<T, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifyError::Duplicate(tgt) => write!(f, "{} is listed more than once", tgt),
+            VerifyError::Missing { target, dependency }
+                => write!(f, "{} depends on {}, which is not listed", target, dependency),
+            VerifyError::OutOfOrder { target, dependency }
+                => write!(f, "{} depends on {}, which is listed after it", target, dependency),
+            VerifyError::UserDef(err) => fmt::Display::fmt(err, f),
+        }
+    }
+}
+
+impl<T, E> std::error::Error for VerifyError<T, E>
+where T: fmt::Debug + fmt::Display, E: std::error::Error + 'static {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            VerifyError::UserDef(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Checks that an order lists every target once, after all of its dependencies, using a
+/// producer function.
+///
+/// Dependencies are produced again for every listed target, so this can check orders from
+/// anywhere, e.g in property tests of producer functions.
+pub fn verify<T, F, I, E>(order: &[T], mut f: F) -> Result<(), VerifyError<T, E>>
+where T: Eq + Hash + Clone, F: FnMut(&T) -> I, I: IntoIterator<Item = Result<T, E>> {
+    // The position of every target.
+    let mut pos = HashMap::with_capacity(order.len());
+    for (i, tgt) in order.iter().enumerate() {
+        if pos.insert(tgt, i).is_some() {
+            return Err(VerifyError::Duplicate(tgt.clone()))
+        }
+    }
+    for (i, tgt) in order.iter().enumerate() {
+        for dep in (f)(tgt) {
+            let dep = dep.map_err(VerifyError::UserDef)?;
+            match pos.get(&dep) {
+                Some(&j) if j < i => {},
+                Some(_) => return Err(VerifyError::OutOfOrder {
+                    target: tgt.clone(),
+                    dependency: dep,
+                }),
+                None => return Err(VerifyError::Missing {
+                    target: tgt.clone(),
+                    dependency: dep,
+                }),
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,113 @@
+//! Visitors and observers for dependency maps.
+
+use std::cell::RefCell;
+use std::hash::Hash;
+
+use crate::{CyclePolicy, DepMap, Error, Resolution};
+
+/// A stateful alternative to producer functions.
+pub trait DepVisitor<T> {
+    /// A user-defined error.
+    type Error;
+
+    /// Returns the dependencies of a target.
+    fn dependencies(&mut self, tgt: &T) -> Result<Vec<T>, Self::Error>;
+
+    /// Called whenever a target is completed (i.e added to the result list).
+    fn on_complete(&mut self, _tgt: &T) {}
+
+    /// Decides what to do about a cycle, given its chain of active targets.
+    ///
+    /// By default, cycles fail.
+    fn on_cycle(&mut self, _chain: &[T]) -> Resolution {
+        Resolution::Fail
+    }
+}
+
+/// Something that happened while working on a dependency map.
+#[derive(Debug)]
+pub enum Event<'a, T> {
+    /// A target's dependencies are about to be produced.
+    Expanding(&'a T),
+    /// A target was completed, with its index in the result list.
+    Completed(&'a T, usize),
+    /// A cyclic dependency was found, with the chain of active targets in the cycle.
+    CycleDetected(&'a [T]),
+}
+
+impl<T: Eq + Hash + Clone> DepMap<T> {
+    /// Runs through a whole dependency map using a visitor.
+    pub fn drive<V>(initial: Vec<T>, visitor: &mut V) -> Result<Vec<T>, Error<T, V::Error>>
+    where V: DepVisitor<T> {
+        // Both the producer and the cycle policy need the visitor, but never at the same time.
+        let visitor = RefCell::new(visitor);
+        let mut policy = CyclePolicy::callback(|chain: &[T]| visitor.borrow_mut().on_cycle(chain));
+        // The current map.
+        let mut state = Self::new(initial);
+        loop {
+            match state.destroy() {
+                Ok(res) => break Ok(res),
+                Err(map) => state = map,
+            };
+
+            // Not empty; Process, reporting newly completed targets
+            let len = state.result.len();
+            let res = state.expand(|tgt| {
+                let (deps, err) = match visitor.borrow_mut().dependencies(tgt) {
+                    Ok(deps) => (deps, None),
+                    Err(err) => (Vec::new(), Some(err)),
+                };
+                err.map(Err).into_iter().chain(deps.into_iter().map(Ok))
+            }, &mut policy)?;
+            for tgt in &state.result[len..] {
+                visitor.borrow_mut().on_complete(tgt);
+            }
+            if let Some((pos, dep)) = res {
+                break Err(Error::CyclicDep(state.take_cycle(pos, dep)))
+            }
+        }
+    }
+
+    /// Runs through a whole dependency map, reporting what happens to an observer.
+    pub fn process_observed<O, F, I, E>(initial: Vec<T>, observer: O, f: F)
+        -> Result<Vec<T>, Error<T, E>>
+    where O: FnMut(Event<'_, T>), F: FnMut(&T) -> I, I: IntoIterator<Item = Result<T, E>> {
+        Self::new(initial).finish_observed(CyclePolicy::Fail, observer, f)
+    }
+
+    /// Runs through the rest of the map using the given cycle policy, reporting what happens to
+    /// an observer.
+    pub(crate) fn finish_observed<O, F, I, E>(
+        self,
+        mut policy: CyclePolicy<T>,
+        observer: O,
+        mut f: F,
+    ) -> Result<Vec<T>, Error<T, E>>
+    where O: FnMut(Event<'_, T>), F: FnMut(&T) -> I, I: IntoIterator<Item = Result<T, E>> {
+        // Both the loop and the cycle policy need the observer, but never at the same time.
+        let observer = RefCell::new(observer);
+        let mut policy = CyclePolicy::callback(|chain: &[T]| {
+            (observer.borrow_mut())(Event::CycleDetected(chain));
+            policy.resolve(|| chain.iter().collect())
+        });
+        // The current map.
+        let mut state = self;
+        loop {
+            match state.destroy() {
+                Ok(res) => break Ok(res),
+                Err(map) => state = map,
+            };
+
+            // Not empty; Process, reporting newly completed targets
+            (observer.borrow_mut())(Event::Expanding(state.current().unwrap()));
+            let len = state.result.len();
+            let res = state.expand(&mut f, &mut policy)?;
+            for (idx, tgt) in state.result.iter().enumerate().skip(len) {
+                (observer.borrow_mut())(Event::Completed(tgt, idx));
+            }
+            if let Some((pos, dep)) = res {
+                break Err(Error::CyclicDep(state.take_cycle(pos, dep)))
+            }
+        }
+    }
+}
@@ -0,0 +1,111 @@
+//! Watching dependency maps for changes.
+
+use std::collections::HashSet;
+use std::hash::Hash;
+use std::mem;
+use std::sync::mpsc;
+
+use crate::{DepMap, Error};
+
+/// A long-lived resolution, worked out again as targets change, e.g for `--watch` modes.
+///
+/// After the initial resolution, callers report changed targets, and the watcher works out the
+/// ordering of everything affected: the changed targets, everything depending on them, and any
+/// new dependencies they now have. It is returned, and emitted to every callback and
+/// subscriber.
+#[allow(clippy::type_complexity)]
+pub struct Watcher<'a, T: Eq + Hash + Clone, F> {
+    /// The map, recording edges.
+    map: DepMap<T>,
+    /// The producer function.
+    resolver: F,
+    /// Targets worked out again before a failure, and not yet emitted.
+    carry: Vec<T>,
+    /// The callbacks to emit orderings to.
+    callbacks: Vec<Box<dyn FnMut(&[T]) + 'a>>,
+    /// The subscribers to emit orderings to.
+    subscribers: Vec<mpsc::Sender<Vec<T>>>,
+}
+
+impl<'a, T, F, I, E> Watcher<'a, T, F>
+where T: Eq + Hash + Clone, F: FnMut(&T) -> I, I: IntoIterator<Item = Result<T, E>> {
+    /// Creates a new [`Watcher`], resolving an initial list using a producer function.
+    pub fn new(initial: Vec<T>, mut resolver: F) -> Result<Self, Error<T, E>> {
+        let mut map = DepMap::new(initial).with_edges();
+        map.add_all(&mut resolver)?;
+        Ok(Self {
+            map,
+            resolver,
+            carry: Vec::new(),
+            callbacks: Vec::new(),
+            subscribers: Vec::new(),
+        })
+    }
+
+    /// Adds a callback, called with the ordering of the affected targets after every change.
+    pub fn with_callback<G: FnMut(&[T]) + 'a>(mut self, f: G) -> Self {
+        self.callbacks.push(Box::new(f));
+        self
+    }
+
+    /// Subscribes to the ordering of the affected targets after every change.
+    ///
+    /// Subscribers are forgotten once their receiver is dropped.
+    pub fn subscribe(&mut self) -> mpsc::Receiver<Vec<T>> {
+        let (tx, rx) = mpsc::channel();
+        self.subscribers.push(tx);
+        rx
+    }
+
+    /// Every target, in dependency order.
+    pub fn order(&self) -> &[T] {
+        self.map.result()
+    }
+
+    /// The underlying map, e.g for querying edges.
+    pub fn map(&self) -> &DepMap<T> {
+        &self.map
+    }
+
+    /// Reports that a target changed, returning the ordering of the affected targets.
+    ///
+    /// See [`changed_all`](Self::changed_all).
+    pub fn changed(&mut self, tgt: T) -> Result<Vec<T>, Error<T, E>> {
+        self.changed_all(Some(tgt))
+    }
+
+    /// Reports that several targets changed, returning the ordering of the affected targets.
+    ///
+    /// Their dependencies are produced again, and the ordering is emitted unless it is empty.
+    /// Targets not resolved yet are ignored. If producing dependencies fails, nothing is emitted;
+    /// the failed target stays pending, and is produced again with the next change, along with
+    /// what was worked out so far.
+    pub fn changed_all<J: IntoIterator<Item = T>>(&mut self, tgts: J)
+        -> Result<Vec<T>, Error<T, E>> {
+        for tgt in tgts {
+            self.map.invalidate(&tgt);
+        }
+        let before = self.map.result.len();
+        let res = self.map.add_all(&mut self.resolver);
+
+        // Keep what was worked out before the last failure, unless it is being redone.
+        let redone = self.map.result[before..].iter().collect::<HashSet<_>>();
+        let done = &self.map.done;
+        let mut affected = mem::take(&mut self.carry).into_iter()
+            .filter(|tgt| done.contains(tgt) && !redone.contains(tgt))
+            .collect::<Vec<_>>();
+        affected.extend(self.map.result[before..].iter().cloned());
+        if let Err(err) = res {
+            self.carry = affected;
+            return Err(err)
+        }
+
+        if !affected.is_empty() {
+            for f in &mut self.callbacks {
+                (f)(&affected);
+            }
+            self.subscribers.retain(|tx| tx.send(affected.clone()).is_ok());
+        }
+        Ok(affected)
+    }
+}
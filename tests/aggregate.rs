@@ -0,0 +1,36 @@
+use depmap::{DepMap, Error};
+
+fn deps(tgt: &&'static str) -> Vec<Result<&'static str, String>> {
+    match *tgt {
+        "a" => vec![Ok("b"), Ok("c")],
+        "b" => vec![Ok("x"), Err("b failed".to_string())],
+        "e" => vec![Ok("a"), Ok("d")],
+        "f" => vec![Err("f failed".to_string())],
+        "y" => vec![Ok("y")],
+        _ => vec![],
+    }
+}
+
+#[test]
+fn process_aggregating_leaves_out_failed_targets() {
+    let (order, errors) = DepMap::process_aggregating(vec!["e", "f", "g"], deps).unwrap();
+    // `b` failed, so `a` and `e` depending on it are left out; `x` is dropped with `b`'s
+    // dependencies.
+    assert_eq!(order, ["c", "d", "g"]);
+    assert_eq!(errors, [("b", "b failed".to_string()), ("f", "f failed".to_string())]);
+}
+
+#[test]
+fn process_aggregating_without_errors() {
+    let (order, errors) = DepMap::process_aggregating(vec!["c", "d"], deps).unwrap();
+    assert_eq!(order, ["c", "d"]);
+    assert!(errors.is_empty());
+}
+
+#[test]
+fn process_aggregating_fails_on_cycles() {
+    match DepMap::process_aggregating(vec!["y"], deps) {
+        Err(Error::CyclicDep(cycle)) => assert_eq!(cycle.chain, ["y"]),
+        res => panic!("unexpected result: {:?}", res),
+    }
+}
@@ -0,0 +1,16 @@
+use depmap::DepMap;
+
+#[test]
+fn batches_hold_the_next_targets_expanded() {
+    let mut batches = Vec::new();
+    let order = DepMap::process_batched(vec![0, 1, 2, 3, 4, 5], 2, |tgts: &[&u32]| {
+        batches.push(tgts.iter().map(|&&tgt| tgt).collect::<Vec<_>>());
+        Ok::<_, ()>(vec![Vec::new(); tgts.len()])
+    }).unwrap();
+
+    // Each target is fetched along with the one expanded after it, so no batch is wasted.
+    assert_eq!(batches.len(), 3);
+    for (batch, expanded) in batches.iter().zip(order.chunks(2)) {
+        assert_eq!(batch, expanded);
+    }
+}
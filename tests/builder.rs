@@ -0,0 +1,63 @@
+use std::cell::RefCell;
+use std::convert::Infallible;
+
+use depmap::{CyclePolicy, DepMap, Error, Event, Limit};
+
+fn deps(tgt: &&'static str) -> Vec<Result<&'static str, Infallible>> {
+    match *tgt {
+        "all" => vec![Ok("a"), Ok("b")],
+        "a" => vec![Ok("c"), Ok("docs")],
+        "b" => vec![Ok("c"), Ok("b")],
+        _ => vec![],
+    }
+}
+
+#[test]
+fn build_applies_every_option() {
+    let builder = DepMap::builder().done(vec!["c"]).record_edges().stable_order();
+    let mut map = builder.build(vec!["a"]);
+    map.add_all(deps).unwrap();
+    assert_eq!(map.result(), ["docs", "a"]);
+    assert_eq!(map.deps_of(&"a"), ["c", "docs"]);
+
+    let mut map = DepMap::builder().max_depth(1).build(vec!["a"]);
+    assert!(matches!(map.add_all(deps), Err(Error::LimitExceeded(Limit::Depth(1)))));
+    let mut map = DepMap::builder().max_nodes(2).build(vec!["a"]);
+    assert!(matches!(map.add_all(deps), Err(Error::LimitExceeded(Limit::Nodes(2)))));
+}
+
+#[test]
+fn process_applies_every_option() {
+    let completed = RefCell::new(Vec::new());
+    let res = DepMap::builder()
+        .stable_order()
+        .processing()
+        .cycle_policy(CyclePolicy::SkipEdge)
+        .skip_if(|tgt| *tgt == "docs")
+        .phony_if(|tgt| *tgt == "all")
+        .observer(|event| if let Event::Completed(tgt, _) = event {
+            completed.borrow_mut().push(*tgt);
+        })
+        .process(vec!["all"], deps)
+        .unwrap();
+    assert_eq!(res, ["c", "a", "b"]);
+    assert_eq!(*completed.borrow(), ["c", "a", "b", "all"]);
+}
+
+#[test]
+fn process_dedups_by_key() {
+    let deps = |tgt: &String| -> Vec<Result<String, Infallible>> {
+        match tgt.as_str() {
+            "app" => vec![Ok("Lib".to_string()), Ok("util".to_string())],
+            "util" => vec![Ok("lib".to_string()), Ok("LIB".to_string())],
+            _ => vec![],
+        }
+    };
+    let res = DepMap::builder()
+        .done(vec!["UTIL".to_string()])
+        .processing()
+        .dedup_by(|tgt: &String| tgt.to_lowercase())
+        .process(vec!["app".to_string(), "APP".to_string()], deps)
+        .unwrap();
+    assert_eq!(res, ["Lib", "app"]);
+}
@@ -0,0 +1,71 @@
+use std::convert::Infallible;
+use std::sync::Barrier;
+use std::thread;
+
+use depmap::ConcurrentDepMap;
+
+fn deps(tgt: &&'static str) -> Vec<Result<&'static str, Infallible>> {
+    match *tgt {
+        "r1" | "r2" => vec![Ok("s")],
+        "s" => vec![Ok("t")],
+        _ => vec![],
+    }
+}
+
+/// Hands out and completes every ready target, in order.
+fn drain(map: &ConcurrentDepMap<&'static str>) -> Vec<&'static str> {
+    let mut order = Vec::new();
+    while let Some(tgt) = map.next_ready() {
+        assert!(map.complete(&tgt));
+        order.push(tgt);
+    }
+    order
+}
+
+#[test]
+fn add_root_shares_dependencies_between_threads() {
+    let map = ConcurrentDepMap::new();
+    // Both roots are produced before either is added.
+    let barrier = Barrier::new(2);
+    thread::scope(|scope| {
+        for root in ["r1", "r2"] {
+            let (map, barrier) = (&map, &barrier);
+            scope.spawn(move || map.add_root(root, |tgt| {
+                if *tgt == root {
+                    barrier.wait();
+                }
+                deps(tgt)
+            }).unwrap());
+        }
+    });
+
+    let order = drain(&map);
+    assert_eq!(order.len(), 4);
+    assert_eq!(order[..2], ["t", "s"]);
+    assert!(order[2..].contains(&"r1") && order[2..].contains(&"r2"));
+    assert!(map.is_finished());
+}
+
+#[test]
+fn add_root_while_running() {
+    let map = ConcurrentDepMap::new();
+    map.add_root("s", deps).unwrap();
+    assert_eq!(map.next_ready(), Some("t"));
+    assert_eq!(map.running(), 1);
+    assert!(!map.complete(&"s"));
+
+    // `s` is already added, so only the root is.
+    map.add_root("r1", |tgt| {
+        assert_ne!(*tgt, "s");
+        deps(tgt)
+    }).unwrap();
+    assert_eq!(map.next_ready(), None);
+    assert!(map.complete(&"t"));
+    assert!(!map.complete(&"t"));
+    assert_eq!(drain(&map), ["s", "r1"]);
+    assert!(map.is_finished());
+
+    // Completed targets are not handed out again.
+    map.add_root("r2", deps).unwrap();
+    assert_eq!(drain(&map), ["r2"]);
+}
@@ -0,0 +1,67 @@
+#![cfg(feature = "derive")]
+
+use depmap::{DepMap, Dependencies, DependencyProvider};
+
+/// A target holding its dependencies in a named field.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Dependencies)]
+struct Named {
+    name: &'static str,
+    #[dependencies]
+    deps: Vec<Named>,
+}
+
+/// A target holding its dependencies in a tuple field.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Dependencies)]
+struct Tuple(&'static str, #[dependencies] Vec<Tuple>);
+
+/// A target producing its dependencies with a method.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Dependencies)]
+#[dependencies(method = "deps")]
+enum Method {
+    A,
+    B,
+    C,
+}
+
+impl Method {
+    fn deps(&self) -> Vec<Method> {
+        match self {
+            Method::A => vec![Method::B, Method::C],
+            Method::B => vec![Method::C],
+            Method::C => vec![],
+        }
+    }
+}
+
+fn named(name: &'static str, deps: Vec<Named>) -> Named {
+    Named { name, deps }
+}
+
+#[test]
+fn derive_named_field() {
+    let c = named("c", vec![]);
+    let b = named("b", vec![c.clone()]);
+    let a = named("a", vec![b.clone(), c.clone()]);
+    assert_eq!(a.dependencies(), a.deps);
+    assert_eq!(DepMap::process_auto(vec![a.clone()]).unwrap(), [c, b, a]);
+}
+
+#[test]
+fn derive_tuple_field() {
+    let b = Tuple("b", vec![]);
+    let a = Tuple("a", vec![b.clone()]);
+    assert_eq!(a.dependencies(), a.1);
+    assert_eq!(DepMap::process_auto(vec![a.clone()]).unwrap(), [b, a]);
+}
+
+#[test]
+fn derive_method() {
+    assert_eq!(Method::A.dependencies(), [Method::B, Method::C]);
+    assert_eq!(DepMap::process_auto(vec![Method::A]).unwrap(),
+        [Method::C, Method::B, Method::A]);
+}
+
+#[test]
+fn derive_errors() {
+    trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,95 @@
+use depmap::{DepMap, DotError};
+
+/// Every recorded edge of a map, sorted.
+fn edges(map: &DepMap<String>) -> Vec<(&str, &str)> {
+    let mut edges = map.edges()
+        .map(|(tgt, dep)| (tgt.as_str(), dep.as_str()))
+        .collect::<Vec<_>>();
+    edges.sort_unstable();
+    edges
+}
+
+/// Every known target of a map, sorted.
+fn nodes(map: &DepMap<String>) -> Vec<String> {
+    let mut nodes = map.toposort().unwrap();
+    nodes.sort_unstable();
+    nodes
+}
+
+fn error(src: &str) -> DotError {
+    DepMap::from_dot(src).unwrap_err()
+}
+
+#[test]
+fn from_dot_skips_comments() {
+    let map = DepMap::from_dot("\
+# 1 \"preprocessed.dot\"
+digraph { // a comment
+    a -> b; /* a comment
+    spanning lines */ b -> c
+    # not a preprocessor line, since it is not at the start
+}").unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(nodes(&map), ["a", "b", "c"]);
+    assert_eq!(edges(&map), [("a", "b"), ("b", "c")]);
+}
+
+#[test]
+fn from_dot_reads_quoted_and_html_ids() {
+    let map = DepMap::from_dot(r#"digraph {
+        "with \"quotes\"" -> "back\\slash";
+        "con\
+tinued" -> <<b>html</b>>;
+    }"#).unwrap();
+    assert_eq!(nodes(&map), ["<b>html</b>", "back\\slash", "continued", "with \"quotes\""]);
+    assert_eq!(edges(&map), [("continued", "<b>html</b>"), ("with \"quotes\"", "back\\slash")]);
+}
+
+#[test]
+fn from_dot_fans_out_groups() {
+    let map = DepMap::from_dot("digraph { {a b} -> c -> subgraph s { d e } }").unwrap();
+    assert_eq!(edges(&map), [("a", "c"), ("b", "c"), ("c", "d"), ("c", "e")]);
+}
+
+#[test]
+fn from_dot_skips_ports_and_attributes() {
+    let map = DepMap::from_dot("strict digraph g {
+        rankdir = LR;
+        node [shape=box]; edge [color=red, style=dashed]
+        a:out:s -> b:in [label=\"x\"; weight=2] [color=blue];
+        c [style=filled]
+    }").unwrap();
+    assert_eq!(nodes(&map), ["a", "b", "c"]);
+    assert_eq!(edges(&map), [("a", "b")]);
+}
+
+#[test]
+fn from_dot_rejects_undirected_graphs() {
+    let err = error("graph { a -- b }");
+    assert_eq!(err.message, "undirected graphs are not supported");
+    let err = error("digraph {\n a -> b\n b -- c\n}");
+    assert_eq!(err.message, "undirected edges are not supported");
+    assert_eq!(err.line, 3);
+}
+
+#[test]
+fn from_dot_reports_error_lines() {
+    assert_eq!(error("digraph {\n a -> b\n c -> \n}").line, 4);
+    assert_eq!(error("digraph {\n /* open\n\n").line, 2);
+    assert_eq!(error("digraph {\n a -> <open\n\n").line, 2);
+    assert_eq!(error("digraph {\n\n \"open\n }").line, 3);
+    // At the end of input, errors are reported at the last token.
+    assert_eq!(error("digraph {\n a [b=c\n").to_string(), "line 2: expected ']'");
+    assert_eq!(error("digraph {\n a -> ?\n}").line, 2);
+}
+
+#[test]
+fn to_dot_round_trips() {
+    let map = DepMap::from_edges(vec![
+        ("a".to_string(), "b \"quoted\"".to_string()),
+        ("a".to_string(), "c\\d".to_string()),
+        ("b \"quoted\"".to_string(), "c\\d".to_string()),
+    ]);
+    let back = DepMap::from_dot(&map.to_dot()).unwrap();
+    assert_eq!(nodes(&back), nodes(&map));
+    assert_eq!(edges(&back), edges(&map));
+}
@@ -0,0 +1,68 @@
+use std::fmt;
+
+use depmap::{DepMap, Error, Failure};
+
+/// An error caused by another.
+#[derive(Debug)]
+struct Outer(fmt::Error);
+
+impl fmt::Display for Outer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "could not read manifest")
+    }
+}
+
+impl std::error::Error for Outer {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+/// Every message in an error's chain of sources.
+fn messages(err: &dyn std::error::Error) -> Vec<String> {
+    let mut messages = vec![err.to_string()];
+    let mut source = err.source();
+    while let Some(err) = source {
+        messages.push(err.to_string());
+        source = err.source();
+    }
+    messages
+}
+
+#[test]
+fn user_errors_are_transparent() {
+    let err = Error::<&str, _>::UserDef(Outer(fmt::Error));
+    assert_eq!(messages(&err), ["could not read manifest", &fmt::Error.to_string()]);
+}
+
+#[test]
+fn failures_give_context() {
+    let err = Failure { target: "c", chain: vec!["a", "b"], error: Outer(fmt::Error) };
+    assert_eq!(messages(&err), [
+        "while resolving c (required by a -> b)",
+        "could not read manifest",
+        &fmt::Error.to_string(),
+    ]);
+}
+
+#[test]
+fn process_fallible_reports_chain() {
+    let deps = |tgt: &&'static str| match *tgt {
+        "a" => vec![Ok("b")],
+        "b" => vec![Ok("d"), Ok("c")],
+        "c" => vec![Ok("d"), Err(Outer(fmt::Error))],
+        _ => vec![],
+    };
+    let err = DepMap::process_fallible(vec!["a"], deps).unwrap_err();
+    match &err {
+        Error::UserDef(failure) => {
+            assert_eq!(failure.target, "c");
+            assert_eq!(failure.chain, ["a", "b"]);
+        },
+        err => panic!("unexpected error: {:?}", err),
+    }
+    assert_eq!(messages(&err)[..2], [
+        "while resolving c (required by a -> b)",
+        "could not read manifest",
+    ]);
+}
@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::panic;
+
+use depmap::{DepMap, ExecOptions};
+
+fn deps(tgt: &u32) -> Vec<Result<u32, Infallible>> {
+    (0..*tgt).map(Ok).collect()
+}
+
+fn worker(tgt: &u32) -> Result<u32, Infallible> {
+    if *tgt == 2 {
+        panic!("worker panicked");
+    }
+    Ok(*tgt)
+}
+
+#[test]
+fn scheduler_hands_out_ready_targets() {
+    // Every target depends on every smaller one.
+    let mut sched = DepMap::schedule(vec![2, 3], deps).unwrap();
+    assert_eq!(sched.next_ready_where(|&tgt| tgt != 0), None);
+    assert_eq!(sched.next_ready(), Some(0));
+    assert_eq!(sched.next_ready(), None);
+    assert_eq!(sched.running(), 1);
+    assert!(!sched.is_finished());
+
+    // Targets not running are not completed.
+    assert!(!sched.complete(&1));
+    assert!(!sched.complete(&4));
+    assert!(sched.complete(&0));
+    assert!(!sched.complete(&0));
+    assert_eq!(sched.running(), 0);
+
+    assert_eq!(sched.next_ready_where(|&tgt| tgt % 2 == 1), Some(1));
+    assert!(sched.complete(&1));
+    assert_eq!(sched.next_ready_where(|&tgt| tgt == 3), None);
+    assert_eq!(sched.next_ready_where(|&tgt| tgt == 2), Some(2));
+    assert!(!sched.is_finished());
+    assert!(sched.complete(&2));
+    assert_eq!(sched.next_ready(), Some(3));
+    assert!(!sched.is_finished());
+    assert!(sched.complete(&3));
+    assert!(sched.is_finished());
+    assert_eq!(sched.next_ready(), None);
+}
+
+#[test]
+fn run_parallel_passes_worker_panics_on() {
+    let res = panic::catch_unwind(|| DepMap::run_parallel(vec![4], deps, worker, 4));
+    assert!(res.is_err());
+}
+
+#[test]
+fn run_parallel_tagged_passes_worker_panics_on() {
+    let caps = HashMap::from([("cpu", 2)]);
+    let res = panic::catch_unwind(|| {
+        DepMap::run_parallel_tagged(vec![4], deps, worker, 4, |_| vec!["cpu"], &caps)
+    });
+    assert!(res.is_err());
+}
+
+#[test]
+fn execute_with_passes_worker_panics_on() {
+    let plan = DepMap::resolve(vec![4], deps).unwrap();
+    let options = ExecOptions {
+        jobs: 4,
+        retries: 1,
+    };
+    let res = panic::catch_unwind(|| plan.execute_with(options, worker));
+    assert!(res.is_err());
+}
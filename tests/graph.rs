@@ -0,0 +1,80 @@
+use depmap::{DepMap, Error};
+
+#[test]
+fn all_cycles_finds_self_loops() {
+    let map = DepMap::from_edges(vec![("a", "a"), ("a", "b"), ("b", "b")]);
+    assert_eq!(map.all_cycles(), [[&"a"], [&"b"]]);
+}
+
+#[test]
+fn all_cycles_finds_cycles_sharing_a_target() {
+    let map = DepMap::from_edges(vec![("a", "b"), ("b", "a"), ("b", "c"), ("c", "b")]);
+    assert_eq!(map.all_cycles(), [[&"a", &"b"], [&"b", &"c"]]);
+}
+
+#[test]
+fn all_cycles_finds_nested_cycles_in_order() {
+    // `a -> b -> a` is nested in `a -> b -> c -> a`; `d -> e -> d` is separate.
+    let map = DepMap::from_edges(vec![
+        ("a", "b"), ("b", "c"), ("b", "a"), ("c", "a"), ("d", "e"), ("e", "d"),
+    ]);
+    assert_eq!(map.all_cycles(), [vec![&"a", &"b", &"c"], vec![&"a", &"b"], vec![&"d", &"e"]]);
+}
+
+#[test]
+fn all_cycles_of_acyclic_graph() {
+    let map = DepMap::from_edges(vec![("a", "b"), ("a", "c"), ("b", "c")]);
+    assert!(map.all_cycles().is_empty());
+}
+
+#[test]
+fn transitive_reduction_drops_shortcuts() {
+    let map = DepMap::from_edges(vec![
+        ("a", "b"), ("a", "c"), ("a", "d"), ("b", "d"), ("c", "d"),
+    ]);
+    assert_eq!(map.transitive_reduction().unwrap(),
+        [("b", "d"), ("c", "d"), ("a", "b"), ("a", "c")]);
+}
+
+#[test]
+fn transitive_reduction_rejects_cycles() {
+    let map = DepMap::from_edges(vec![("a", "b"), ("b", "c"), ("c", "a")]);
+    match map.transitive_reduction() {
+        Err(Error::CyclicDep(cycle)) => assert_eq!(cycle.chain.len(), 3),
+        res => panic!("unexpected result: {:?}", res),
+    }
+}
+
+#[test]
+fn dominators_of_diamond() {
+    let map = DepMap::from_edges(vec![("r", "a"), ("r", "b"), ("a", "c"), ("b", "c"), ("c", "d")]);
+    let doms = map.dominators(&"r").unwrap();
+    assert_eq!(doms.root(), &"r");
+    assert_eq!(doms.immediate_dominator(&"r"), None);
+    assert_eq!(doms.immediate_dominator(&"a"), Some(&"r"));
+    assert_eq!(doms.immediate_dominator(&"c"), Some(&"r"));
+    assert_eq!(doms.dominators_of(&"d"), [&"c", &"r"]);
+    assert!(!doms.dominates(&"a", &"c"));
+    assert_eq!(doms.dominated_by(&"c"), [&"d"]);
+}
+
+#[test]
+fn dominators_of_chain() {
+    let map = DepMap::from_edges(vec![("r", "a"), ("a", "b"), ("b", "c")]);
+    let doms = map.dominators(&"r").unwrap();
+    assert_eq!(doms.dominators_of(&"c"), [&"b", &"a", &"r"]);
+    assert_eq!(doms.dominated_by(&"a"), [&"b", &"c"]);
+    assert!(map.dominators(&"x").is_none());
+}
+
+#[test]
+fn dominators_of_cycle() {
+    // `a` and `b` depend on each other; `c` is reached both through `b` and directly.
+    let map = DepMap::from_edges(vec![("r", "a"), ("a", "b"), ("b", "a"), ("b", "c"), ("r", "c")]);
+    let doms = map.dominators(&"r").unwrap();
+    assert_eq!(doms.immediate_dominator(&"a"), Some(&"r"));
+    assert_eq!(doms.immediate_dominator(&"b"), Some(&"a"));
+    assert_eq!(doms.immediate_dominator(&"c"), Some(&"r"));
+    assert!(doms.dominates(&"a", &"b"));
+    assert!(!doms.dominates(&"b", &"a"));
+}
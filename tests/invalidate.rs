@@ -0,0 +1,42 @@
+use std::convert::Infallible;
+
+use depmap::DepMap;
+
+fn deps(tgt: &&'static str) -> Vec<Result<&'static str, Infallible>> {
+    match *tgt {
+        "b" => vec![Ok("c")],
+        _ => vec![],
+    }
+}
+
+#[test]
+fn invalidate_keeps_pending_work() {
+    let mut map = DepMap::new(vec!["a", "b"]).with_edges().with_stable_order();
+    map.add(deps).unwrap();
+    assert_eq!(map.result(), ["a"]);
+    assert_eq!(map.invalidate(&"a"), 1);
+    map.add_all(deps).unwrap();
+    let mut result = map.result().to_vec();
+    result.sort_unstable();
+    assert_eq!(result, ["a", "b", "c"]);
+}
+
+#[test]
+fn invalidate_holds_back_active_dependents() {
+    // `d` is expanded and waiting on `x` when `a`, which it also depends on, is invalidated.
+    let deps = |tgt: &&'static str| -> Vec<Result<&'static str, Infallible>> {
+        match *tgt {
+            "d" => vec![Ok("a"), Ok("x")],
+            _ => vec![],
+        }
+    };
+    let mut map = DepMap::new(vec!["d"]).with_edges().with_stable_order();
+    map.add(deps).unwrap();
+    map.add(deps).unwrap();
+    assert_eq!(map.result(), ["a"]);
+    map.invalidate(&"a");
+    map.add_all(deps).unwrap();
+    let pos = |tgt| map.result().iter().position(|t| *t == tgt).unwrap();
+    assert!(pos("a") < pos("d"));
+    assert!(pos("x") < pos("d"));
+}
@@ -0,0 +1,43 @@
+use std::convert::Infallible;
+
+use depmap::{DepMap, DepMapConfig, Error, Limit};
+
+fn deps(tgt: &&'static str) -> Vec<Result<&'static str, Infallible>> {
+    match *tgt {
+        "a" => vec![Ok("b"), Ok("c")],
+        "b" => vec![Ok("c")],
+        "d" => vec![Ok("b"), Ok("c")],
+        _ => vec![],
+    }
+}
+
+fn config(max_depth: Option<usize>, max_nodes: Option<usize>) -> DepMapConfig {
+    DepMapConfig { max_depth, max_nodes }
+}
+
+#[test]
+fn node_limit_counts_distinct_targets() {
+    let res = DepMap::process_limited(vec!["a"], config(None, Some(3)), deps).unwrap();
+    assert_eq!(res, ["c", "b", "a"]);
+}
+
+#[test]
+fn node_limit_counts_diamonds_once() {
+    // `b` and `c` are shared by `a` and `d`.
+    let res = DepMap::process_limited(vec!["a", "d"], config(None, Some(4)), deps).unwrap();
+    assert_eq!(res.len(), 4);
+    match DepMap::process_limited(vec!["a", "d"], config(None, Some(3)), deps) {
+        Err(Error::LimitExceeded(Limit::Nodes(3))) => {},
+        res => panic!("unexpected result: {:?}", res),
+    }
+}
+
+#[test]
+fn depth_limit_counts_active_targets() {
+    let res = DepMap::process_limited(vec!["a"], config(Some(3), None), deps).unwrap();
+    assert_eq!(res, ["c", "b", "a"]);
+    match DepMap::process_limited(vec!["a"], config(Some(2), None), deps) {
+        Err(Error::LimitExceeded(Limit::Depth(2))) => {},
+        res => panic!("unexpected result: {:?}", res),
+    }
+}
@@ -0,0 +1,53 @@
+use depmap::{DepMap, LogEvent};
+
+fn deps(tgt: &&'static str) -> Vec<Result<&'static str, &'static str>> {
+    match *tgt {
+        "a" => vec![Ok("b"), Ok("c")],
+        "b" => vec![Ok("d")],
+        "c" => vec![Ok("a"), Ok("d")],
+        "e" => vec![Ok("d")],
+        _ => vec![],
+    }
+}
+
+fn acyclic(tgt: &&'static str) -> Vec<Result<&'static str, &'static str>> {
+    deps(tgt).into_iter().filter(|dep| *dep != Ok("a")).collect()
+}
+
+#[test]
+fn replay_reproduces_map() {
+    let mut map = DepMap::new(vec!["a"]).with_edges().with_log();
+    // `a` fails once, before producing anything.
+    assert!(map.add(|_| vec![Err("flaky")]).is_err());
+    map.add(deps).unwrap();
+    map.add(deps).unwrap();
+    map.add(deps).unwrap();
+    map.add(deps).unwrap();
+    // `c` depends on `a`; Skip it
+    assert_eq!(map.add_skipping(deps).unwrap(), [vec!["a", "c"]]);
+    map.extend(vec!["e"]);
+    map.add_all(deps).unwrap();
+    map.invalidate(&"d");
+
+    let events = map.log().unwrap().events();
+    assert!(events.iter().any(|event| matches!(event, LogEvent::Cycle { .. })));
+    assert!(events.contains(&LogEvent::Invalidated("d")));
+    let mut replayed = DepMap::replay(map.log().unwrap()).unwrap();
+    assert_eq!(replayed, map);
+    assert_eq!(replayed.result(), map.result());
+
+    // Redoing `c` would find the cycle again; Leave it out
+    map.add_all(acyclic).unwrap();
+    replayed.add_all(acyclic).unwrap();
+    assert_eq!(replayed.result(), map.result());
+    assert_eq!(replayed.log(), map.log());
+}
+
+#[test]
+fn replay_starts_from_log_start() {
+    let mut map = DepMap::new(vec!["a"]).with_edges();
+    map.add(deps).unwrap();
+    let mut map = map.with_log();
+    map.add_all(acyclic).unwrap();
+    assert_eq!(DepMap::replay(map.log().unwrap()).unwrap(), map);
+}
@@ -0,0 +1,52 @@
+use std::convert::Infallible;
+
+use depmap::{DepMap, MergeError};
+
+fn deps(tgt: &&'static str) -> Vec<Result<&'static str, Infallible>> {
+    match *tgt {
+        "a" => vec![Ok("b")],
+        "c" => vec![Ok("b"), Ok("d")],
+        _ => vec![],
+    }
+}
+
+#[test]
+fn merge_combines_progress() {
+    let mut ours = DepMap::new(vec!["a"]).with_edges().with_log();
+    ours.add_all(deps).unwrap();
+    let mut theirs = DepMap::new(vec!["c"]).with_edges();
+    theirs.add(deps).unwrap();
+    theirs.add(deps).unwrap();
+    assert_eq!(theirs.result(), ["b"]);
+
+    let mut map = ours.merge(theirs).unwrap();
+    assert_eq!(map.result(), ["b", "a"]);
+    assert_eq!(map.active_chain().collect::<Vec<_>>(), [&"c"]);
+    assert!(map.log().unwrap().events().is_empty());
+    map.add_all(deps).unwrap();
+    assert_eq!(map.result(), ["b", "a", "d", "c"]);
+    assert_eq!(map.deps_of(&"a"), ["b"]);
+    assert_eq!(map.deps_of(&"c"), ["b", "d"]);
+}
+
+#[test]
+fn merge_rejects_targets_active_here_but_done_there() {
+    let mut ours = DepMap::new(vec!["a"]);
+    ours.add(deps).unwrap();
+    // Only `a` is done there.
+    let mut theirs = DepMap::new(vec!["a"]);
+    theirs.add_all(|_| Vec::<Result<_, Infallible>>::new()).unwrap();
+    assert_eq!(ours.merge(theirs), Err(MergeError::Active("a")));
+}
+
+#[test]
+fn merge_rejects_different_dependencies() {
+    let mut ours = DepMap::new(vec!["a"]).with_edges();
+    ours.add_all(deps).unwrap();
+    let mut theirs = DepMap::new(vec!["a"]).with_edges();
+    theirs.add_all(|tgt: &&'static str| match *tgt {
+        "a" => vec![Ok::<_, Infallible>("d")],
+        _ => vec![],
+    }).unwrap();
+    assert_eq!(ours.merge(theirs), Err(MergeError::Deps("a")));
+}
@@ -0,0 +1,23 @@
+#![cfg(feature = "persist")]
+
+use std::convert::Infallible;
+use std::fs;
+
+use depmap::{Cache, DepMap};
+
+#[test]
+fn cache_round_trips_non_string_targets() {
+    let path = std::env::temp_dir().join(format!("depmap-cache-{}.json", std::process::id()));
+    let deps = |&(x, y): &(u32, u32)| -> Vec<Result<(u32, u32), Infallible>> {
+        (0..x).map(|x| Ok((x, y))).collect()
+    };
+    let roots = vec![(3, 1), (2, 5)];
+    let plan = DepMap::resolve(roots.clone(), deps).unwrap();
+
+    let mut cache = Cache::load(&path).unwrap();
+    cache.insert(&roots, plan.clone());
+    cache.save().unwrap();
+    let cache = Cache::<(u32, u32)>::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(cache.get(&roots), Some(&plan));
+}
@@ -0,0 +1,25 @@
+#![cfg(feature = "serde")]
+
+use std::convert::Infallible;
+
+use depmap::DepMap;
+
+#[test]
+fn map_round_trips_non_string_targets() {
+    let deps = |&(x, y): &(u32, u32)| -> Vec<Result<(u32, u32), Infallible>> {
+        (0..x).map(|x| Ok((x, y))).collect()
+    };
+    // Part way through, so there are active targets, edges and parents.
+    let mut map = DepMap::new(vec![(3, 0), (2, 1)]).with_edges();
+    for _ in 0..3 {
+        map.add(deps).unwrap();
+    }
+    assert!(!map.is_empty());
+
+    let json = serde_json::to_string(&map).unwrap();
+    let mut back = serde_json::from_str::<DepMap<(u32, u32)>>(&json).unwrap();
+    assert_eq!(back, map);
+    map.add_all(deps).unwrap();
+    back.add_all(deps).unwrap();
+    assert_eq!(back.result(), map.result());
+}
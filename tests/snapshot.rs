@@ -0,0 +1,88 @@
+use std::convert::Infallible;
+
+use depmap::{DepMap, StaleSnapshot};
+
+fn deps(tgt: &&'static str) -> Vec<Result<&'static str, Infallible>> {
+    match *tgt {
+        "a" => vec![Ok("b"), Ok("c")],
+        "b" => vec![Ok("d")],
+        _ => vec![],
+    }
+}
+
+#[test]
+fn restore_rolls_back_progress_and_edges() {
+    let mut map = DepMap::new(vec!["a"]).with_edges();
+    map.add(deps).unwrap();
+    let snapshot = map.snapshot();
+    map.add_all(deps).unwrap();
+    assert_eq!(map.result(), ["d", "b", "c", "a"]);
+
+    map.restore(&snapshot).unwrap();
+    assert!(map.result().is_empty());
+    assert_eq!(map.deps_of(&"a"), ["b", "c"]);
+    assert!(map.deps_of(&"b").is_empty());
+    map.add_all(deps).unwrap();
+    assert_eq!(map.result(), ["d", "b", "c", "a"]);
+}
+
+#[test]
+fn restore_puts_back_edges_of_failed_expansion() {
+    let mut fail = true;
+    let mut map = DepMap::new(vec!["a"]).with_edges();
+    // `a` produces `x` and then fails once.
+    let mut flaky = |tgt: &&'static str| -> Vec<Result<&'static str, &'static str>> {
+        if std::mem::take(&mut fail) {
+            vec![Ok("x"), Err("flaky")]
+        } else {
+            deps(tgt).into_iter().map(|dep| Ok(dep.unwrap())).collect()
+        }
+    };
+    assert!(map.add(&mut flaky).is_err());
+    assert_eq!(map.deps_of(&"a"), ["x"]);
+    let snapshot = map.snapshot();
+    map.add(&mut flaky).unwrap();
+    assert_eq!(map.deps_of(&"a"), ["b", "c"]);
+
+    map.restore(&snapshot).unwrap();
+    assert_eq!(map.deps_of(&"a"), ["x"]);
+}
+
+#[test]
+fn restore_rolls_back_log() {
+    let mut map = DepMap::new(vec!["a"]).with_log();
+    map.add(deps).unwrap();
+    let snapshot = map.snapshot();
+    let log = map.log().unwrap().clone();
+    map.add_all(deps).unwrap();
+    assert_ne!(map.log(), Some(&log));
+
+    map.restore(&snapshot).unwrap();
+    assert_eq!(map.log(), Some(&log));
+    assert_eq!(DepMap::replay(&log).unwrap(), map);
+}
+
+#[test]
+fn extend_invalidates_snapshots() {
+    let mut map = DepMap::new(vec!["a"]);
+    map.add(deps).unwrap();
+    let snapshot = map.snapshot();
+    map.extend(vec!["e"]);
+    assert_eq!(map.restore(&snapshot), Err(StaleSnapshot));
+    map.add_all(deps).unwrap();
+    assert!(map.result().contains(&"e"));
+}
+
+#[test]
+fn undo_add_only_undoes_add() {
+    let mut map = DepMap::new(vec!["a"]).with_undo();
+    map.add(deps).unwrap();
+    map.add(deps).unwrap();
+    assert!(map.undo_add());
+    assert!(!map.undo_add());
+
+    map.add(deps).unwrap();
+    map.add_all(deps).unwrap();
+    assert!(!map.undo_add());
+    assert_eq!(map.result(), ["d", "b", "c", "a"]);
+}
@@ -0,0 +1,70 @@
+use std::convert::Infallible;
+
+use depmap::{DepMap, StepOutcome};
+
+fn deps(tgt: &&'static str) -> Vec<Result<&'static str, Infallible>> {
+    match *tgt {
+        "a" => vec![Ok("b"), Ok("c"), Ok("x")],
+        "b" => vec![Ok("c")],
+        "x" => vec![Ok("c"), Ok("x")],
+        _ => vec![],
+    }
+}
+
+fn outcome(target: &'static str) -> StepOutcome<&'static str> {
+    StepOutcome {
+        target,
+        new: Vec::new(),
+        duplicate: Vec::new(),
+        done: Vec::new(),
+        cycle: None,
+        completed: Vec::new(),
+    }
+}
+
+#[test]
+fn step_describes_changes() {
+    let mut map = DepMap::new(vec!["a", "x"]);
+    assert_eq!(map.step(deps).unwrap(), Some(StepOutcome {
+        new: vec!["b", "c"],
+        duplicate: vec!["x"],
+        ..outcome("a")
+    }));
+    assert_eq!(map.step(deps).unwrap(), Some(StepOutcome {
+        duplicate: vec!["c"],
+        ..outcome("b")
+    }));
+    assert_eq!(map.step(deps).unwrap(), Some(StepOutcome {
+        completed: vec!["c", "b"],
+        ..outcome("c")
+    }));
+
+    // `x` depends on itself, so it is kept without its dependencies.
+    let step = map.step(deps).unwrap().unwrap();
+    assert_eq!(step.target, "x");
+    assert_eq!(step.done, ["c"]);
+    assert_eq!(step.cycle.unwrap().chain, ["x"]);
+    assert!(step.completed.is_empty());
+
+    let step = map.step(|_| vec![Ok::<_, Infallible>("c")]).unwrap().unwrap();
+    assert_eq!(step.target, "x");
+    assert_eq!(step.done, ["c"]);
+    assert_eq!(step.completed, ["x", "a"]);
+}
+
+#[test]
+fn step_finishes_like_add() {
+    let mut stepped = DepMap::new(vec!["a"]);
+    let mut added = stepped.clone();
+    // Leave out `x` depending on itself.
+    let acyclic = |tgt: &&'static str| -> Vec<_> {
+        deps(tgt).into_iter().filter(|dep| dep != &Ok(*tgt)).collect()
+    };
+    while stepped.step(acyclic).unwrap().is_some() {
+        added.add(acyclic).unwrap();
+        assert_eq!(stepped, added);
+    }
+    assert!(stepped.is_empty());
+    assert_eq!(stepped.result(), ["c", "b", "x", "a"]);
+    assert_eq!(stepped.step(deps).unwrap(), None);
+}
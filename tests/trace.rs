@@ -0,0 +1,63 @@
+#![cfg(feature = "tracing")]
+
+use std::convert::Infallible;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use depmap::DepMap;
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+/// Counts events from lazy producers outside any span.
+#[derive(Default)]
+struct Counter {
+    /// The number of spans entered.
+    depth: AtomicUsize,
+    /// The number of producer events outside any span.
+    outside: Arc<AtomicUsize>,
+    /// The number of spans created.
+    spans: AtomicUsize,
+}
+
+impl Subscriber for Counter {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, _: &Attributes<'_>) -> Id {
+        Id::from_u64(self.spans.fetch_add(1, Ordering::SeqCst) as u64 + 1)
+    }
+
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        if event.metadata().target() == "producer" && self.depth.load(Ordering::SeqCst) == 0 {
+            self.outside.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn enter(&self, _: &Id) {
+        self.depth.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn exit(&self, _: &Id) {
+        self.depth.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn lazy_producers_are_traced_in_their_span() {
+    let counter = Counter::default();
+    let outside = counter.outside.clone();
+    tracing::subscriber::with_default(counter, || {
+        DepMap::process_traced(vec![3u32], |&tgt| {
+            (0..tgt).map(|dep| {
+                tracing::trace!(target: "producer", dep);
+                Ok::<_, Infallible>(dep)
+            })
+        }).unwrap();
+    });
+    assert_eq!(outside.load(Ordering::SeqCst), 0);
+}
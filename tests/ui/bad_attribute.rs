@@ -0,0 +1,7 @@
+use depmap::Dependencies;
+
+#[derive(Clone, Dependencies)]
+#[dependencies(function = "deps")]
+struct Target;
+
+fn main() {}
@@ -0,0 +1,8 @@
+use depmap::Dependencies;
+
+#[derive(Clone, Dependencies)]
+enum Target {
+    A,
+}
+
+fn main() {}
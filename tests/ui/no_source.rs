@@ -0,0 +1,8 @@
+use depmap::Dependencies;
+
+#[derive(Clone, Dependencies)]
+struct Target {
+    deps: Vec<Target>,
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use depmap::Dependencies;
+
+#[derive(Clone, Dependencies)]
+struct Target {
+    #[dependencies]
+    deps: Vec<Target>,
+    #[dependencies]
+    more: Vec<Target>,
+}
+
+fn main() {}
@@ -0,0 +1,36 @@
+use std::cell::Cell;
+
+use depmap::Watcher;
+
+#[test]
+fn watcher_recovers_after_producer_error() {
+    // `b` fails once after being changed, then depends on `c` and `d`.
+    let changed = Cell::new(false);
+    let failed = Cell::new(false);
+    let resolver = |tgt: &&'static str| -> Vec<Result<&'static str, &'static str>> {
+        match *tgt {
+            "a" => vec![Ok("b")],
+            "b" if !changed.get() => vec![],
+            "b" if !failed.get() => {
+                failed.set(true);
+                vec![Err("flaky")]
+            },
+            "b" => vec![Ok("c"), Ok("d")],
+            _ => vec![],
+        }
+    };
+    let mut watcher = Watcher::new(vec!["a", "x"], resolver).unwrap();
+    assert_eq!(watcher.order(), ["b", "a", "x"]);
+
+    changed.set(true);
+    assert!(watcher.changed("b").is_err());
+    let affected = watcher.changed("x").unwrap();
+    assert_eq!(watcher.map().deps_of(&"b"), ["c", "d"]);
+    for tgt in ["c", "d", "b", "a", "x"] {
+        assert!(affected.contains(&tgt), "{} not affected", tgt);
+    }
+
+    // Changes to the new dependencies reach `b`.
+    let affected = watcher.changed("c").unwrap();
+    assert_eq!(affected, ["c", "b", "a"]);
+}